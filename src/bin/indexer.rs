@@ -2,7 +2,7 @@ use std::{fs, path::PathBuf};
 
 use bunnyfont::{
     char_transforms::{CharMirror, CharRotation},
-    integrations::ggez::{GgBunnyChar, GgBunnyFont, GgBunnyFontBatch},
+    integrations::ggez::{window_to_screen, GgBunnyChar, GgBunnyFont, GgBunnyFontBatch},
 };
 use failure::Fallible;
 use ggez::{
@@ -92,17 +92,27 @@ impl Indexer {
 impl EventHandler<ggez::GameError> for Indexer {
     fn mouse_button_down_event(
         &mut self,
-        _ctx: &mut Context,
+        ctx: &mut Context,
         _button: MouseButton,
         x: f32,
         y: f32,
     ) {
-        let (tile_width, tile_height) = self.font_batch.tile_size(self.opts.scaling as f32);
+        let charset_dimensions = self.font_batch.font().charset_dimensions();
 
-        let char_x = (x / tile_width).floor() as usize;
-        let char_y = (y / tile_height).floor() as usize;
+        let point = window_to_screen(ctx, (x, y));
 
-        let width = self.font_batch.font().charset_dimensions().0;
+        let (char_x, char_y) = match self.font_batch.pick_cell(
+            ctx,
+            point,
+            self.opts.scaling as f32,
+            DrawParam::default(),
+            charset_dimensions,
+        ) {
+            Some(cell) => cell,
+            None => return,
+        };
+
+        let width = charset_dimensions.0;
 
         let index = char_y * width + char_x;
 
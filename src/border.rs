@@ -0,0 +1,482 @@
+use crate::{
+    char::BunnyChar,
+    char_transforms::{compose_orientations, CharMirror, CharRotation},
+    charmap::CharMap,
+    grid::BunnyGrid,
+    rect::GridRect,
+    text::TextOptions,
+    traits::color::Color,
+};
+
+//A glyph index along with the orientation it is drawn at
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BorderGlyph {
+    pub index: usize,
+    pub rotation: CharRotation,
+    pub mirror: CharMirror,
+}
+
+impl BorderGlyph {
+    pub fn new(index: usize) -> Self {
+        Self {
+            index,
+            rotation: CharRotation::None,
+            mirror: CharMirror::None,
+        }
+    }
+
+    //Rotates the glyph clockwise on screen on top of its current orientation
+    pub fn rotated(self, rotation: CharRotation) -> Self {
+        let (rotation, mirror) =
+            compose_orientations((self.rotation, self.mirror), (rotation, CharMirror::None));
+
+        Self {
+            rotation,
+            mirror,
+            ..self
+        }
+    }
+
+    pub fn to_char<C>(&self, template: BunnyChar<C>) -> BunnyChar<C>
+    where
+        C: Color,
+    {
+        template
+            .index(self.index)
+            .rotation(self.rotation)
+            .mirror(self.mirror)
+    }
+}
+
+//A border defined by its top left corner, top edge and left tee glyphs, the other sides are rotations of these
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BorderStyle {
+    pub corner: BorderGlyph,
+    pub edge: BorderGlyph,
+    pub tee: Option<BorderGlyph>,
+}
+
+impl BorderStyle {
+    pub fn new(corner: BorderGlyph, edge: BorderGlyph, tee: Option<BorderGlyph>) -> Self {
+        Self { corner, edge, tee }
+    }
+
+    //Builds a style from the unicode chars of its top left corner, top edge and left tee
+    pub fn from_chars(
+        charmap: &CharMap,
+        corner: char,
+        edge: char,
+        tee: Option<char>,
+    ) -> Option<Self> {
+        let tee = match tee {
+            Some(tee) => Some(BorderGlyph::new(charmap.exact_index_of(tee)?)),
+            None => None,
+        };
+
+        Some(Self::new(
+            BorderGlyph::new(charmap.exact_index_of(corner)?),
+            BorderGlyph::new(charmap.exact_index_of(edge)?),
+            tee,
+        ))
+    }
+
+    pub fn single(charmap: &CharMap) -> Option<Self> {
+        Self::from_chars(charmap, '┌', '─', Some('├'))
+    }
+
+    pub fn double(charmap: &CharMap) -> Option<Self> {
+        Self::from_chars(charmap, '╔', '═', Some('╠'))
+    }
+
+    //Falls back to the single line corner for charmaps without rounded corners, such as code page 437
+    pub fn rounded(charmap: &CharMap) -> Option<Self> {
+        Self::from_chars(charmap, '╭', '─', Some('├')).or_else(|| Self::single(charmap))
+    }
+
+    pub fn top_left(&self) -> BorderGlyph {
+        self.corner
+    }
+
+    pub fn top_right(&self) -> BorderGlyph {
+        self.corner.rotated(CharRotation::Rotation90)
+    }
+
+    pub fn bottom_right(&self) -> BorderGlyph {
+        self.corner.rotated(CharRotation::Rotation180)
+    }
+
+    pub fn bottom_left(&self) -> BorderGlyph {
+        self.corner.rotated(CharRotation::Rotation270)
+    }
+
+    pub fn top(&self) -> BorderGlyph {
+        self.edge
+    }
+
+    pub fn right(&self) -> BorderGlyph {
+        self.edge.rotated(CharRotation::Rotation90)
+    }
+
+    pub fn bottom(&self) -> BorderGlyph {
+        self.edge.rotated(CharRotation::Rotation180)
+    }
+
+    pub fn left(&self) -> BorderGlyph {
+        self.edge.rotated(CharRotation::Rotation270)
+    }
+
+    //The tees where separators meet the border, falling back to the edges when there is no tee glyph
+    pub fn left_tee(&self) -> BorderGlyph {
+        self.tee.unwrap_or_else(|| self.left())
+    }
+
+    pub fn top_tee(&self) -> BorderGlyph {
+        self.tee
+            .map_or_else(|| self.top(), |tee| tee.rotated(CharRotation::Rotation90))
+    }
+
+    pub fn right_tee(&self) -> BorderGlyph {
+        self.tee.map_or_else(
+            || self.right(),
+            |tee| tee.rotated(CharRotation::Rotation180),
+        )
+    }
+
+    pub fn bottom_tee(&self) -> BorderGlyph {
+        self.tee.map_or_else(
+            || self.bottom(),
+            |tee| tee.rotated(CharRotation::Rotation270),
+        )
+    }
+
+    //A nine-slice drawing this border around an optional fill
+    pub fn nine_slice<C>(&self, template: BunnyChar<C>, fill: Option<BunnyChar<C>>) -> NineSlice<C>
+    where
+        C: Color,
+    {
+        let piece = |glyph: BorderGlyph| Some(glyph.to_char(template));
+
+        NineSlice {
+            cells: [
+                [
+                    piece(self.top_left()),
+                    piece(self.top()),
+                    piece(self.top_right()),
+                ],
+                [piece(self.left()), fill, piece(self.right())],
+                [
+                    piece(self.bottom_left()),
+                    piece(self.bottom()),
+                    piece(self.bottom_right()),
+                ],
+            ],
+        }
+    }
+}
+
+//Nine cells in [row][column] order whose corners are drawn once, edges repeated along the sides and centre repeated inside
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NineSlice<C> {
+    pub cells: [[Option<BunnyChar<C>>; 3]; 3],
+}
+
+impl<C> NineSlice<C>
+where
+    C: Color,
+{
+    //Takes a nine-slice from the 3x3 cells of a grid starting at the given position
+    pub fn from_grid(grid: &BunnyGrid<C>, x: usize, y: usize) -> Self {
+        let mut cells = [[None; 3]; 3];
+
+        for (row_index, row) in cells.iter_mut().enumerate() {
+            for (column_index, cell) in row.iter_mut().enumerate() {
+                *cell = grid.get(x + column_index, y + row_index).copied();
+            }
+        }
+
+        Self { cells }
+    }
+}
+
+impl<C> BunnyGrid<C>
+where
+    C: Color,
+{
+    //Draws a nine-slice stretched over an area, empty pieces leave the grid untouched
+    pub fn draw_nine_slice(&mut self, area: GridRect, nine_slice: &NineSlice<C>) {
+        if area.is_empty() {
+            return;
+        }
+
+        let slice_index = |position: usize, start: usize, end: usize| {
+            if position == start {
+                0
+            } else if position == end - 1 {
+                2
+            } else {
+                1
+            }
+        };
+
+        for (x, y) in area.cells() {
+            let row = slice_index(y, area.y, area.bottom());
+            let column = slice_index(x, area.x, area.right());
+
+            if let Some(cell) = nine_slice.cells[row][column] {
+                self.set_clipped(x as i32, y as i32, Some(cell));
+            }
+        }
+    }
+
+    //Draws a border around the edge of an area, leaving the inside untouched
+    pub fn draw_box(&mut self, area: GridRect, style: &BorderStyle, template: BunnyChar<C>) {
+        self.draw_nine_slice(area, &style.nine_slice(template, None));
+    }
+
+    //Draws a border with a title on its top edge and clears its inside, returning the inner area
+    //The title is printed with the given options, which decide its alignment and how it ends when it is cut off
+    pub fn draw_panel(
+        &mut self,
+        charmap: &CharMap,
+        area: GridRect,
+        style: &BorderStyle,
+        template: BunnyChar<C>,
+        title: Option<&str>,
+        title_options: &TextOptions,
+    ) -> GridRect {
+        let fill = template.index(charmap.index_of(' ').unwrap_or(template.index));
+
+        self.draw_nine_slice(area, &style.nine_slice(template, Some(fill)));
+
+        if let Some(title) = title {
+            let title_area = GridRect::new(area.x + 2, area.y, area.width.saturating_sub(4), 1);
+
+            self.print(charmap, title_area, title, template, title_options);
+        }
+
+        area.inset(1)
+    }
+
+    //Draws a horizontal separator across a box at the given row, joining it to the box's sides with tees
+    pub fn draw_separator(
+        &mut self,
+        area: GridRect,
+        y: usize,
+        style: &BorderStyle,
+        template: BunnyChar<C>,
+    ) {
+        if area.width < 2 {
+            return;
+        }
+
+        for x in area.x + 1..area.right() - 1 {
+            self.set_clipped(x as i32, y as i32, Some(style.top().to_char(template)));
+        }
+
+        self.set_clipped(
+            area.x as i32,
+            y as i32,
+            Some(style.left_tee().to_char(template)),
+        );
+        self.set_clipped(
+            (area.right() - 1) as i32,
+            y as i32,
+            Some(style.right_tee().to_char(template)),
+        );
+    }
+
+    //Draws a vertical separator down a box at the given column, joining it to the box's top and bottom with tees
+    pub fn draw_vertical_separator(
+        &mut self,
+        area: GridRect,
+        x: usize,
+        style: &BorderStyle,
+        template: BunnyChar<C>,
+    ) {
+        if area.height < 2 {
+            return;
+        }
+
+        for y in area.y + 1..area.bottom() - 1 {
+            self.set_clipped(x as i32, y as i32, Some(style.left().to_char(template)));
+        }
+
+        self.set_clipped(
+            x as i32,
+            area.y as i32,
+            Some(style.top_tee().to_char(template)),
+        );
+        self.set_clipped(
+            x as i32,
+            (area.bottom() - 1) as i32,
+            Some(style.bottom_tee().to_char(template)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::color::TestColor;
+
+    fn template() -> BunnyChar<TestColor> {
+        BunnyChar::new(
+            0,
+            TestColor([255; 4]),
+            None,
+            CharRotation::None,
+            CharMirror::None,
+        )
+    }
+
+    fn glyph_at(grid: &BunnyGrid<TestColor>, x: usize, y: usize) -> Option<BorderGlyph> {
+        grid.get(x, y).map(|bunny_char| BorderGlyph {
+            index: bunny_char.index,
+            rotation: bunny_char.rotation,
+            mirror: bunny_char.mirror,
+        })
+    }
+
+    #[test]
+    fn rounded_falls_back_to_single_without_rounded_corners() {
+        let cp437 = CharMap::cp437();
+        assert_eq!(BorderStyle::rounded(&cp437), BorderStyle::single(&cp437));
+
+        let charmap = CharMap::cp437().with_char('╭', 0x100);
+        let rounded = BorderStyle::rounded(&charmap).unwrap();
+        assert_eq!(rounded.corner, BorderGlyph::new(0x100));
+        assert_eq!(rounded.edge, BorderGlyph::new(0xc4));
+    }
+
+    #[test]
+    fn missing_glyphs_give_no_style() {
+        assert_eq!(BorderStyle::single(&CharMap::new()), None);
+    }
+
+    #[test]
+    fn sides_are_rotations_of_the_top_left() {
+        let style = BorderStyle::single(&CharMap::cp437()).unwrap();
+
+        assert_eq!(style.top_right().rotation, CharRotation::Rotation90);
+        assert_eq!(style.bottom_right().rotation, CharRotation::Rotation180);
+        assert_eq!(style.bottom_left().rotation, CharRotation::Rotation270);
+        assert_eq!(style.right().rotation, CharRotation::Rotation90);
+        assert_eq!(style.top_tee().rotation, CharRotation::Rotation90);
+        assert_eq!(style.top_tee().index, 0xc3);
+    }
+
+    #[test]
+    fn tees_fall_back_to_edges() {
+        let style = BorderStyle::new(BorderGlyph::new(1), BorderGlyph::new(2), None);
+
+        assert_eq!(style.left_tee(), style.left());
+        assert_eq!(style.bottom_tee(), style.bottom());
+    }
+
+    #[test]
+    fn draw_box_leaves_the_inside_untouched() {
+        let style = BorderStyle::single(&CharMap::cp437()).unwrap();
+        let mut grid = BunnyGrid::new((4, 3));
+
+        grid.draw_box(GridRect::new(0, 0, 4, 3), &style, template());
+
+        assert_eq!(glyph_at(&grid, 0, 0), Some(style.top_left()));
+        assert_eq!(glyph_at(&grid, 1, 0), Some(style.top()));
+        assert_eq!(glyph_at(&grid, 3, 0), Some(style.top_right()));
+        assert_eq!(glyph_at(&grid, 3, 1), Some(style.right()));
+        assert_eq!(glyph_at(&grid, 3, 2), Some(style.bottom_right()));
+        assert_eq!(glyph_at(&grid, 0, 2), Some(style.bottom_left()));
+        assert_eq!(glyph_at(&grid, 1, 1), None);
+        assert_eq!(glyph_at(&grid, 2, 1), None);
+    }
+
+    #[test]
+    fn draw_panel_titles_and_fills() {
+        let charmap = CharMap::cp437();
+        let style = BorderStyle::single(&charmap).unwrap();
+        let mut grid = BunnyGrid::new((8, 4));
+
+        let inner = grid.draw_panel(
+            &charmap,
+            GridRect::from_dimensions((8, 4)),
+            &style,
+            template(),
+            Some("Hi"),
+            &TextOptions::default(),
+        );
+
+        assert_eq!(inner, GridRect::new(1, 1, 6, 2));
+        assert_eq!(
+            grid.get(2, 0).map(|bunny_char| bunny_char.index),
+            Some(b'H' as usize)
+        );
+        assert_eq!(
+            grid.get(4, 0).map(|bunny_char| bunny_char.index),
+            Some(0xc4)
+        );
+        assert_eq!(
+            grid.get(3, 2).map(|bunny_char| bunny_char.index),
+            Some(b' ' as usize)
+        );
+    }
+
+    #[test]
+    fn long_panel_titles_are_cut_off() {
+        let charmap = CharMap::cp437();
+        let style = BorderStyle::single(&charmap).unwrap();
+        let title = |options: &TextOptions| {
+            let mut grid = BunnyGrid::new((8, 3));
+
+            grid.draw_panel(
+                &charmap,
+                GridRect::from_dimensions((8, 3)),
+                &style,
+                template(),
+                Some("Inventory"),
+                options,
+            );
+
+            (0..8)
+                .map(|x| {
+                    grid.get(x, 0)
+                        .and_then(|bunny_char| charmap.char_of(bunny_char.index))
+                        .unwrap()
+                })
+                .collect::<String>()
+        };
+
+        //The top right corner is the top left glyph rotated, so it reads back as the same char
+        assert_eq!(title(&TextOptions::default()), "┌─Inve─┌");
+        assert_eq!(
+            title(&TextOptions::default().ellipsis(Some('~'))),
+            "┌─Inv~─┌"
+        );
+    }
+
+    #[test]
+    fn separators_join_with_tees() {
+        let style = BorderStyle::single(&CharMap::cp437()).unwrap();
+        let mut grid = BunnyGrid::new((5, 5));
+        let area = GridRect::from_dimensions((5, 5));
+
+        grid.draw_separator(area, 2, &style, template());
+        grid.draw_vertical_separator(area, 2, &style, template());
+
+        assert_eq!(glyph_at(&grid, 0, 2), Some(style.left_tee()));
+        assert_eq!(glyph_at(&grid, 4, 2), Some(style.right_tee()));
+        assert_eq!(glyph_at(&grid, 2, 0), Some(style.top_tee()));
+        assert_eq!(glyph_at(&grid, 2, 4), Some(style.bottom_tee()));
+        assert_eq!(glyph_at(&grid, 2, 1), Some(style.left()));
+    }
+
+    #[test]
+    fn nine_slice_round_trips_through_a_grid() {
+        let style = BorderStyle::double(&CharMap::cp437()).unwrap();
+        let nine_slice = style.nine_slice(template(), Some(template()));
+        let mut grid = BunnyGrid::new((3, 3));
+
+        grid.draw_nine_slice(GridRect::from_dimensions((3, 3)), &nine_slice);
+
+        assert_eq!(NineSlice::from_grid(&grid, 0, 0), nine_slice);
+    }
+}
@@ -0,0 +1,315 @@
+use crate::{
+    char::BunnyChar,
+    charmap::CharMap,
+    grid::BunnyGrid,
+    shapes::{clip_line, line_points},
+    traits::color::Color,
+};
+
+//How many pixels each cell of a canvas is split into, along with the glyphs that draw them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CanvasMode {
+    //2x2 pixels per cell using quadrant block glyphs
+    Quadrant,
+    //2x3 pixels per cell using sextant block glyphs
+    Sextant,
+    //2x4 pixels per cell using braille glyphs
+    Braille,
+}
+
+const QUADRANT_CHARS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+impl CanvasMode {
+    pub fn cell_size(&self) -> (usize, usize) {
+        match self {
+            CanvasMode::Quadrant => (2, 2),
+            CanvasMode::Sextant => (2, 3),
+            CanvasMode::Braille => (2, 4),
+        }
+    }
+
+    fn pattern_count(&self) -> usize {
+        let (width, height) = self.cell_size();
+        1 << (width * height)
+    }
+
+    //The bit of a cell's pattern that a pixel within the cell sets
+    fn pixel_bit(&self, x: usize, y: usize) -> usize {
+        match self {
+            CanvasMode::Quadrant | CanvasMode::Sextant => y * 2 + x,
+            //Braille dots are numbered down the left column then the right, with the bottom row added last
+            CanvasMode::Braille => match (x, y) {
+                (_, 3) => 6 + x,
+                _ => x * 3 + y,
+            },
+        }
+    }
+
+    //The unicode char drawing a pattern of set pixels
+    pub fn pattern_char(&self, pattern: usize) -> char {
+        match self {
+            CanvasMode::Quadrant => QUADRANT_CHARS[pattern & 0xf],
+            CanvasMode::Sextant => match pattern & 0x3f {
+                0 => ' ',
+                0x15 => '▌',
+                0x2a => '▐',
+                0x3f => '█',
+                //The sextant block skips the patterns that already exist as half and full blocks
+                pattern => {
+                    let skipped = (pattern > 0x15) as u32 + (pattern > 0x2a) as u32;
+                    std::char::from_u32(0x1fb00 + pattern as u32 - 1 - skipped).unwrap()
+                }
+            },
+            CanvasMode::Braille => std::char::from_u32(0x2800 + (pattern & 0xff) as u32).unwrap(),
+        }
+    }
+
+    //Finds a glyph for every pattern, using the closest pattern the charmap has a glyph for when it is missing one
+    pub fn glyph_table(&self, charmap: &CharMap) -> Vec<Option<usize>> {
+        let available: Vec<(usize, usize)> = (0..self.pattern_count())
+            .filter_map(|pattern| {
+                charmap
+                    .exact_index_of(self.pattern_char(pattern))
+                    .map(|index| (pattern, index))
+            })
+            .collect();
+
+        (0..self.pattern_count())
+            .map(|pattern| {
+                available
+                    .iter()
+                    .min_by_key(|(available, _)| (available ^ pattern).count_ones())
+                    .map(|(_, index)| *index)
+            })
+            .collect()
+    }
+}
+
+//A grid of pixels drawn into a grid at several pixels per cell
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubCellCanvas<C> {
+    mode: CanvasMode,
+    width: usize,
+    height: usize,
+    pixels: Vec<Option<C>>,
+}
+
+impl<C> SubCellCanvas<C>
+where
+    C: Color,
+{
+    //Creates a canvas covering the given number of cells
+    pub fn new(mode: CanvasMode, cell_dimensions: (usize, usize)) -> Self {
+        let (cell_width, cell_height) = mode.cell_size();
+        let (width, height) = (
+            cell_dimensions.0 * cell_width,
+            cell_dimensions.1 * cell_height,
+        );
+
+        Self {
+            mode,
+            width,
+            height,
+            pixels: vec![None; width * height],
+        }
+    }
+
+    pub fn mode(&self) -> CanvasMode {
+        self.mode
+    }
+
+    //The dimensions of the canvas in pixels
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<C> {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x]
+        } else {
+            None
+        }
+    }
+
+    //Sets a pixel, pixels outside of the canvas are ignored
+    pub fn set(&mut self, x: i32, y: i32, color: Option<C>) {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            self.pixels[y as usize * self.width + x as usize] = color;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pixels.iter_mut().for_each(|pixel| *pixel = None);
+    }
+
+    pub fn line(&mut self, from: (i32, i32), to: (i32, i32), color: Option<C>) {
+        if let Some((from, to)) = clip_line(from, to, self.dimensions()) {
+            for (x, y) in line_points(from, to) {
+                self.set(x, y, color);
+            }
+        }
+    }
+
+    //Draws the canvas into a grid with its top left cell at the given position
+    //Each cell takes the most common colour of its pixels as its foreground, empty cells are drawn as blank template chars
+    pub fn draw(
+        &self,
+        grid: &mut BunnyGrid<C>,
+        charmap: &CharMap,
+        position: (i32, i32),
+        template: BunnyChar<C>,
+    ) {
+        let glyphs = self.mode.glyph_table(charmap);
+        let (cell_width, cell_height) = self.mode.cell_size();
+        let (position_x, position_y) = position;
+
+        for cell_y in 0..self.height / cell_height {
+            for cell_x in 0..self.width / cell_width {
+                let mut pattern = 0;
+                let mut colors: Vec<(C, usize)> = Vec::new();
+
+                for y in 0..cell_height {
+                    for x in 0..cell_width {
+                        let pixel = self.get(cell_x * cell_width + x, cell_y * cell_height + y);
+
+                        if let Some(color) = pixel {
+                            pattern |= 1 << self.mode.pixel_bit(x, y);
+
+                            match colors.iter_mut().find(|(counted, _)| *counted == color) {
+                                Some((_, count)) => *count += 1,
+                                None => colors.push((color, 1)),
+                            }
+                        }
+                    }
+                }
+
+                let foreground = colors
+                    .iter()
+                    .rev()
+                    .max_by_key(|(_, count)| *count)
+                    .map_or(template.foreground, |(color, _)| *color);
+
+                let index = glyphs[pattern]
+                    .or_else(|| charmap.index_of(' '))
+                    .unwrap_or(template.index);
+
+                grid.set_clipped(
+                    position_x + cell_x as i32,
+                    position_y + cell_y as i32,
+                    Some(template.index(index).foreground(foreground)),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        char_transforms::{CharMirror, CharRotation},
+        traits::color::TestColor,
+    };
+
+    const RED: TestColor = TestColor([255, 0, 0, 255]);
+    const GREEN: TestColor = TestColor([0, 255, 0, 255]);
+    const BLUE: TestColor = TestColor([0, 0, 255, 255]);
+    const WHITE: TestColor = TestColor([255; 4]);
+
+    fn template() -> BunnyChar<TestColor> {
+        BunnyChar::new(99, WHITE, None, CharRotation::None, CharMirror::None)
+    }
+
+    #[test]
+    fn pattern_chars() {
+        assert_eq!(CanvasMode::Quadrant.pattern_char(0b0001), '▘');
+        assert_eq!(CanvasMode::Quadrant.pattern_char(0b1111), '█');
+
+        assert_eq!(CanvasMode::Sextant.pattern_char(0x01), '\u{1fb00}');
+        assert_eq!(CanvasMode::Sextant.pattern_char(0x15), '▌');
+        assert_eq!(CanvasMode::Sextant.pattern_char(0x16), '\u{1fb14}');
+        assert_eq!(CanvasMode::Sextant.pattern_char(0x3e), '\u{1fb3b}');
+
+        let braille = CanvasMode::Braille;
+        assert_eq!(braille.pattern_char(1 << braille.pixel_bit(0, 1)), '⠂');
+        assert_eq!(braille.pattern_char(1 << braille.pixel_bit(1, 3)), '⢀');
+    }
+
+    #[test]
+    fn missing_glyphs_use_the_closest_pattern() {
+        let glyphs = CanvasMode::Quadrant.glyph_table(&CharMap::cp437());
+
+        assert_eq!(glyphs[0b0011], Some(0xdf));
+        assert_eq!(glyphs[0b0111], Some(0xdf));
+        assert_eq!(glyphs[0b1111], Some(0xdb));
+        assert_eq!(glyphs[0b0001], Some(0x20));
+
+        assert!(CanvasMode::Quadrant
+            .glyph_table(&CharMap::new())
+            .iter()
+            .all(Option::is_none));
+    }
+
+    #[test]
+    fn draw_uses_the_most_common_colour() {
+        let charmap = QUADRANT_CHARS
+            .iter()
+            .enumerate()
+            .fold(CharMap::new(), |charmap, (index, ch)| {
+                charmap.with_char(*ch, index)
+            });
+
+        let mut canvas = SubCellCanvas::new(CanvasMode::Quadrant, (3, 1));
+        canvas.set(0, 0, Some(RED));
+        canvas.set(1, 1, Some(RED));
+        canvas.set(0, 1, Some(BLUE));
+        canvas.set(2, 0, Some(GREEN));
+        canvas.set(3, 0, Some(BLUE));
+
+        let mut grid = BunnyGrid::new((4, 1));
+        canvas.draw(&mut grid, &charmap, (1, 0), template());
+
+        let cells: Vec<Option<(usize, TestColor)>> = grid
+            .cells()
+            .map(|(_, bunny_char)| {
+                bunny_char.map(|bunny_char| (bunny_char.index, bunny_char.foreground))
+            })
+            .collect();
+
+        assert_eq!(
+            cells,
+            vec![
+                None,
+                Some((0b1101, RED)),
+                Some((0b0011, GREEN)),
+                Some((0, WHITE)),
+            ]
+        );
+    }
+
+    #[test]
+    fn lines_and_pixels_outside_the_canvas_are_clipped() {
+        let mut canvas = SubCellCanvas::new(CanvasMode::Braille, (1, 1));
+        assert_eq!(canvas.dimensions(), (2, 4));
+
+        canvas.line((0, -2), (0, 5), Some(RED));
+        canvas.set(-1, 0, Some(BLUE));
+        canvas.set(2, 0, Some(BLUE));
+        assert_eq!(canvas.get(5, 5), None);
+
+        let mut grid = BunnyGrid::new((1, 1));
+        canvas.draw(
+            &mut grid,
+            &CharMap::new().with_char('⡇', 1),
+            (0, 0),
+            template(),
+        );
+
+        assert_eq!(grid.get(0, 0).map(|bunny_char| bunny_char.index), Some(1));
+
+        canvas.clear();
+        assert_eq!(canvas.get(0, 0), None);
+    }
+}
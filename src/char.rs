@@ -2,11 +2,11 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    char_transforms::{CharMirror, CharRotation},
+    char_transforms::{compose_orientations, CharMirror, CharRotation},
     traits::color::Color,
 };
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BunnyChar<C> {
     pub index: usize,
@@ -49,4 +49,55 @@ where
     pub fn mirror(self, mirror: CharMirror) -> Self {
         Self { mirror, ..self }
     }
+
+    //Rotates and then mirrors the char on top of its current orientation
+    pub fn oriented(self, rotation: CharRotation, mirror: CharMirror) -> Self {
+        let (rotation, mirror) =
+            compose_orientations((self.rotation, self.mirror), (rotation, mirror));
+
+        Self {
+            rotation,
+            mirror,
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::color::TestColor;
+
+    #[test]
+    fn orienting_builds_on_the_current_orientation() {
+        let bunny_char = BunnyChar::new(
+            1,
+            TestColor([255; 4]),
+            None,
+            CharRotation::Rotation90,
+            CharMirror::None,
+        );
+
+        let rotated = bunny_char.oriented(CharRotation::Rotation180, CharMirror::None);
+        assert_eq!(
+            (rotated.rotation, rotated.mirror),
+            (CharRotation::Rotation270, CharMirror::None)
+        );
+
+        let mirrored = bunny_char.oriented(CharRotation::None, CharMirror::MirrorX);
+        assert_eq!(
+            (mirrored.rotation, mirrored.mirror),
+            compose_orientations(
+                (CharRotation::Rotation90, CharMirror::None),
+                (CharRotation::None, CharMirror::MirrorX)
+            )
+        );
+        assert_eq!(mirrored.index, 1);
+
+        let restored = mirrored.oriented(CharRotation::None, CharMirror::MirrorX);
+        assert_eq!(
+            (restored.rotation, restored.mirror),
+            (CharRotation::Rotation90, CharMirror::None)
+        );
+    }
 }
@@ -70,3 +70,182 @@ impl CharMirror {
         }
     }
 }
+
+pub(crate) type OrientationMatrix = [[i32; 2]; 2];
+
+fn multiply(a: OrientationMatrix, b: OrientationMatrix) -> OrientationMatrix {
+    [
+        [
+            a[0][0] * b[0][0] + a[0][1] * b[1][0],
+            a[0][0] * b[0][1] + a[0][1] * b[1][1],
+        ],
+        [
+            a[1][0] * b[0][0] + a[1][1] * b[1][0],
+            a[1][0] * b[0][1] + a[1][1] * b[1][1],
+        ],
+    ]
+}
+
+impl CharRotation {
+    //Rotations are clockwise on screen, with y pointing down
+    fn matrix(self) -> OrientationMatrix {
+        match self {
+            CharRotation::None => [[1, 0], [0, 1]],
+            CharRotation::Rotation90 => [[0, -1], [1, 0]],
+            CharRotation::Rotation180 => [[-1, 0], [0, -1]],
+            CharRotation::Rotation270 => [[0, 1], [-1, 0]],
+        }
+    }
+}
+
+impl CharMirror {
+    fn matrix(self) -> OrientationMatrix {
+        match self {
+            CharMirror::None => [[1, 0], [0, 1]],
+            CharMirror::MirrorX => [[-1, 0], [0, 1]],
+            CharMirror::MirrorY => [[1, 0], [0, -1]],
+            CharMirror::MirrorBoth => [[-1, 0], [0, -1]],
+        }
+    }
+}
+
+//The matrix taking a point relative to the centre of an upright char to where it is drawn, in rows with y pointing down
+pub(crate) fn orientation_matrix(rotation: CharRotation, mirror: CharMirror) -> OrientationMatrix {
+    multiply(mirror.matrix(), rotation.matrix())
+}
+
+//A char is rotated first and mirrored second, this works out the single rotation and mirror
+//equivalent to orienting a char one way and then another, preferring no mirroring or MirrorX
+pub fn compose_orientations(
+    first: (CharRotation, CharMirror),
+    then: (CharRotation, CharMirror),
+) -> (CharRotation, CharMirror) {
+    let combined = multiply(
+        orientation_matrix(then.0, then.1),
+        orientation_matrix(first.0, first.1),
+    );
+
+    [CharMirror::None, CharMirror::MirrorX]
+        .iter()
+        .flat_map(|mirror| {
+            [
+                CharRotation::None,
+                CharRotation::Rotation90,
+                CharRotation::Rotation180,
+                CharRotation::Rotation270,
+            ]
+            .iter()
+            .map(move |rotation| (*rotation, *mirror))
+        })
+        .find(|(rotation, mirror)| orientation_matrix(*rotation, *mirror) == combined)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROTATIONS: [CharRotation; 4] = [
+        CharRotation::None,
+        CharRotation::Rotation90,
+        CharRotation::Rotation180,
+        CharRotation::Rotation270,
+    ];
+
+    const MIRRORS: [CharMirror; 4] = [
+        CharMirror::None,
+        CharMirror::MirrorX,
+        CharMirror::MirrorY,
+        CharMirror::MirrorBoth,
+    ];
+
+    type Affine = (OrientationMatrix, [i32; 2]);
+
+    //The unit square transforms the ggez integration draws chars with, as rows and a translation
+    fn ggez_rotation(rotation: CharRotation) -> Affine {
+        match rotation {
+            CharRotation::None => ([[1, 0], [0, 1]], [0, 0]),
+            CharRotation::Rotation90 => ([[0, -1], [1, 0]], [1, 0]),
+            CharRotation::Rotation180 => ([[-1, 0], [0, -1]], [1, 1]),
+            CharRotation::Rotation270 => ([[0, 1], [-1, 0]], [0, 1]),
+        }
+    }
+
+    fn ggez_mirror(mirror: CharMirror) -> Affine {
+        match mirror {
+            CharMirror::None => ([[1, 0], [0, 1]], [0, 0]),
+            CharMirror::MirrorX => ([[-1, 0], [0, 1]], [1, 0]),
+            CharMirror::MirrorY => ([[1, 0], [0, -1]], [0, 1]),
+            CharMirror::MirrorBoth => ([[-1, 0], [0, -1]], [1, 1]),
+        }
+    }
+
+    //The affine transform applying inner and then outer
+    fn then(inner: Affine, outer: Affine) -> Affine {
+        let (matrix, translation) = outer;
+
+        (
+            multiply(matrix, inner.0),
+            [
+                matrix[0][0] * inner.1[0] + matrix[0][1] * inner.1[1] + translation[0],
+                matrix[1][0] * inner.1[0] + matrix[1][1] * inner.1[1] + translation[1],
+            ],
+        )
+    }
+
+    //Like the ggez integration, a char is rotated and then mirrored
+    fn ggez_transform((rotation, mirror): (CharRotation, CharMirror)) -> Affine {
+        then(ggez_rotation(rotation), ggez_mirror(mirror))
+    }
+
+    fn orientations() -> impl Iterator<Item = (CharRotation, CharMirror)> {
+        ROTATIONS
+            .iter()
+            .flat_map(|rotation| MIRRORS.iter().map(move |mirror| (*rotation, *mirror)))
+    }
+
+    #[test]
+    fn orientation_matrices_match_the_ggez_transforms() {
+        for orientation in orientations() {
+            assert_eq!(
+                orientation_matrix(orientation.0, orientation.1),
+                ggez_transform(orientation).0
+            );
+        }
+    }
+
+    #[test]
+    fn composing_matches_applying_ggez_transforms_in_turn() {
+        for first in orientations() {
+            for second in orientations() {
+                let composed = compose_orientations(first, second);
+
+                assert_eq!(
+                    ggez_transform(composed),
+                    then(ggez_transform(first), ggez_transform(second)),
+                    "{:?} then {:?}",
+                    first,
+                    second
+                );
+                assert!(matches!(composed.1, CharMirror::None | CharMirror::MirrorX));
+            }
+        }
+    }
+
+    #[test]
+    fn unmirrored_rotations_compose_like_then() {
+        for first in ROTATIONS.iter() {
+            for second in ROTATIONS.iter() {
+                assert_eq!(
+                    compose_orientations((*first, CharMirror::None), (*second, CharMirror::None)),
+                    (first.then(*second), CharMirror::None)
+                );
+            }
+        }
+
+        assert_eq!(
+            CharMirror::MirrorX.then(CharMirror::MirrorY),
+            CharMirror::MirrorBoth
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+
+use crate::char_transforms::{CharMirror, CharRotation};
+
+//Unicode equivalents of every code page 437 glyph, in font index order
+pub const CP437_CHARS: [char; 256] = [
+    ' ', '☺', '☻', '♥', '♦', '♣', '♠', '•', '◘', '○', '◙', '♂', '♀', '♪', '♫', '☼',
+    '►', '◄', '↕', '‼', '¶', '§', '▬', '↨', '↑', '↓', '→', '←', '∟', '↔', '▲', '▼',
+    ' ', '!', '"', '#', '$', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/',
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ':', ';', '<', '=', '>', '?',
+    '@', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O',
+    'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '[', '\\', ']', '^', '_',
+    '`', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o',
+    'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '{', '|', '}', '~', '⌂',
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+//Maps unicode chars and glyph names onto indices of a font
+#[derive(Clone, Debug, Default)]
+pub struct CharMap {
+    chars: HashMap<char, usize>,
+    indices: HashMap<usize, char>,
+    names: HashMap<String, usize>,
+    fallback: Option<usize>,
+}
+
+impl CharMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //A charmap for fonts laid out in code page 437 order, unknown chars fall back to '?'
+    pub fn cp437() -> Self {
+        let mut charmap = Self::new();
+
+        //Printable ASCII goes first so that it wins over the control-range glyphs that share a char
+        for index in (0x20..0x100).chain(0x00..0x20) {
+            charmap.insert_char(CP437_CHARS[index], index);
+        }
+
+        charmap.fallback(Some(b'?' as usize))
+    }
+
+    //Maps a char to an index, the first index registered for a char is the one used for reverse lookups
+    pub fn insert_char(&mut self, ch: char, index: usize) {
+        self.chars.entry(ch).or_insert(index);
+        self.indices.entry(index).or_insert(ch);
+    }
+
+    pub fn insert_name(&mut self, name: &str, index: usize) {
+        self.names.insert(name.to_owned(), index);
+    }
+
+    pub fn with_char(mut self, ch: char, index: usize) -> Self {
+        self.insert_char(ch, index);
+        self
+    }
+
+    pub fn with_name(mut self, name: &str, index: usize) -> Self {
+        self.insert_name(name, index);
+        self
+    }
+
+    pub fn fallback(self, fallback: Option<usize>) -> Self {
+        Self { fallback, ..self }
+    }
+
+    //Looks up the index of a char, using the fallback index for unmapped chars
+    pub fn index_of(&self, ch: char) -> Option<usize> {
+        self.chars.get(&ch).copied().or(self.fallback)
+    }
+
+    //Looks up the index of a char without using the fallback index
+    pub fn exact_index_of(&self, ch: char) -> Option<usize> {
+        self.chars.get(&ch).copied()
+    }
+
+    pub fn char_of(&self, index: usize) -> Option<char> {
+        self.indices.get(&index).copied()
+    }
+
+    //Looks up a glyph by name, falling back to a single char name such as "@"
+    pub fn glyph(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied().or_else(|| {
+            let mut chars = name.chars();
+
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => self.exact_index_of(ch),
+                _ => None,
+            }
+        })
+    }
+}
+
+//The code page 437 code of a char, preferring printable codes over the control range
+pub fn cp437_code(ch: char) -> Option<u8> {
+    (0x20..0x100)
+        .chain(0x00..0x20)
+        .find(|index| CP437_CHARS[*index] == ch)
+        .map(|index| index as u8)
+}
+
+//Each char paired with what it looks like rotated 90 degrees clockwise, and what it looks like mirrored along x
+//Chars that look the same either way are paired with themselves
+#[rustfmt::skip]
+const ORIENTED_CHARS: &[(char, Option<char>, Option<char>)] = &[
+    (' ', Some(' '), Some(' ')), ('█', Some('█'), Some('█')), ('■', Some('■'), Some('■')),
+    ('░', Some('░'), Some('░')), ('▒', Some('▒'), Some('▒')), ('▓', Some('▓'), Some('▓')),
+    ('+', Some('+'), Some('+')), ('o', Some('o'), Some('o')), ('*', Some('*'), Some('*')),
+    ('-', Some('|'), Some('-')), ('|', Some('-'), Some('|')),
+    ('/', Some('\\'), Some('\\')), ('\\', Some('/'), Some('/')),
+    ('^', Some('>'), Some('^')), ('>', Some('v'), Some('<')), ('v', Some('<'), Some('v')), ('<', Some('^'), Some('>')),
+    ('(', None, Some(')')), (')', None, Some('(')), ('[', None, Some(']')), (']', None, Some('[')),
+    ('{', None, Some('}')), ('}', None, Some('{')),
+    ('▀', Some('▐'), Some('▀')), ('▐', Some('▄'), Some('▌')), ('▄', Some('▌'), Some('▄')), ('▌', Some('▀'), Some('▐')),
+    ('↑', Some('→'), Some('↑')), ('→', Some('↓'), Some('←')), ('↓', Some('←'), Some('↓')), ('←', Some('↑'), Some('→')),
+    ('▲', Some('►'), Some('▲')), ('►', Some('▼'), Some('◄')), ('▼', Some('◄'), Some('▼')), ('◄', Some('▲'), Some('►')),
+    ('─', Some('│'), Some('─')), ('│', Some('─'), Some('│')), ('┼', Some('┼'), Some('┼')),
+    ('┌', Some('┐'), Some('┐')), ('┐', Some('┘'), Some('┌')), ('┘', Some('└'), Some('└')), ('└', Some('┌'), Some('┘')),
+    ('├', Some('┬'), Some('┤')), ('┬', Some('┤'), Some('┬')), ('┤', Some('┴'), Some('├')), ('┴', Some('├'), Some('┴')),
+    ('═', Some('║'), Some('═')), ('║', Some('═'), Some('║')), ('╬', Some('╬'), Some('╬')),
+    ('╔', Some('╗'), Some('╗')), ('╗', Some('╝'), Some('╔')), ('╝', Some('╚'), Some('╚')), ('╚', Some('╔'), Some('╝')),
+    ('╠', Some('╦'), Some('╣')), ('╦', Some('╣'), Some('╦')), ('╣', Some('╩'), Some('╠')), ('╩', Some('╠'), Some('╩')),
+];
+
+//The char that looks like a char drawn with the given orientation, if there is one
+pub fn oriented_char(ch: char, rotation: CharRotation, mirror: CharMirror) -> Option<char> {
+    let lookup = |ch: char| ORIENTED_CHARS.iter().find(|(oriented, _, _)| *oriented == ch);
+
+    let rotations = match rotation {
+        CharRotation::None => 0,
+        CharRotation::Rotation90 => 1,
+        CharRotation::Rotation180 => 2,
+        CharRotation::Rotation270 => 3,
+    };
+
+    //Mirroring along y is a half turn followed by mirroring along x, mirroring both ways is just a half turn
+    let (extra_rotations, mirror_x) = match mirror {
+        CharMirror::None => (0, false),
+        CharMirror::MirrorX => (0, true),
+        CharMirror::MirrorY => (2, true),
+        CharMirror::MirrorBoth => (2, false),
+    };
+
+    let mut oriented = ch;
+
+    for _ in 0..(rotations + extra_rotations) % 4 {
+        oriented = lookup(oriented)?.1?;
+    }
+
+    if mirror_x {
+        oriented = lookup(oriented)?.2?;
+    }
+
+    Some(oriented)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cp437_prefers_printable_ascii() {
+        let charmap = CharMap::cp437();
+
+        assert_eq!(charmap.index_of(' '), Some(0x20));
+        assert_eq!(charmap.index_of('A'), Some(0x41));
+        assert_eq!(charmap.index_of('─'), Some(0xc4));
+        assert_eq!(charmap.char_of(0x01), Some('☺'));
+        assert_eq!(charmap.char_of(0x20), Some(' '));
+    }
+
+    #[test]
+    fn unknown_chars_use_the_fallback_only_when_asked() {
+        let charmap = CharMap::cp437();
+
+        assert_eq!(charmap.index_of('✓'), Some(b'?' as usize));
+        assert_eq!(charmap.exact_index_of('✓'), None);
+        assert_eq!(CharMap::new().index_of('✓'), None);
+    }
+
+    #[test]
+    fn glyphs_are_found_by_name_or_single_char() {
+        let charmap = CharMap::cp437().with_name("wall", 0xdb);
+
+        assert_eq!(charmap.glyph("wall"), Some(0xdb));
+        assert_eq!(charmap.glyph("@"), Some(0x40));
+        assert_eq!(charmap.glyph("✓"), None);
+        assert_eq!(charmap.glyph("floor"), None);
+    }
+
+    #[test]
+    fn cp437_codes_prefer_printable_codes() {
+        assert_eq!(cp437_code('A'), Some(0x41));
+        assert_eq!(cp437_code(' '), Some(0x20));
+        assert_eq!(cp437_code('☺'), Some(0x01));
+        assert_eq!(cp437_code('█'), Some(0xdb));
+        assert_eq!(cp437_code('✓'), None);
+    }
+
+    #[test]
+    fn oriented_chars() {
+        let oriented = |ch, rotation| oriented_char(ch, rotation, CharMirror::None);
+        let mirrored = |ch, mirror| oriented_char(ch, CharRotation::None, mirror);
+
+        assert_eq!(oriented('─', CharRotation::Rotation90), Some('│'));
+        assert_eq!(oriented('┌', CharRotation::Rotation90), Some('┐'));
+        assert_eq!(oriented('▲', CharRotation::Rotation180), Some('▼'));
+        assert_eq!(oriented('(', CharRotation::Rotation90), None);
+        assert_eq!(oriented('A', CharRotation::None), Some('A'));
+        assert_eq!(oriented('A', CharRotation::Rotation90), None);
+
+        assert_eq!(mirrored('┌', CharMirror::MirrorY), Some('└'));
+        assert_eq!(mirrored('┌', CharMirror::MirrorBoth), Some('┘'));
+        assert_eq!(mirrored('(', CharMirror::MirrorX), Some(')'));
+    }
+
+    #[test]
+    fn oriented_chars_come_back_around() {
+        for (ch, rotated, mirrored) in ORIENTED_CHARS {
+            if rotated.is_some() {
+                assert_eq!(
+                    oriented_char(*ch, CharRotation::Rotation270, CharMirror::None).and_then(
+                        |ch| oriented_char(ch, CharRotation::Rotation90, CharMirror::None)
+                    ),
+                    Some(*ch)
+                );
+            }
+
+            if let Some(mirrored) = mirrored {
+                assert_eq!(
+                    oriented_char(*mirrored, CharRotation::None, CharMirror::MirrorX),
+                    Some(*ch)
+                );
+            }
+        }
+    }
+}
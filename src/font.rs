@@ -2,7 +2,10 @@ use crate::traits::into_scalar::IntoScalar;
 use crate::traits::lerpable::Lerpable;
 use crate::traits::pixel_indexable::PixelIndexable;
 use crate::char_transforms::{CharMirror, CharRotation};
-use crate::{char::BunnyChar, traits::source_image::SourceImage};
+use crate::{char::BunnyChar, grid::BunnyGrid, traits::{color::Color, source_image::SourceImage}};
+
+//The glyph that is tinted with a cell's background colour and drawn under its glyph, usually a full block
+pub const BACKGROUND_CHAR_INDEX: usize = 0x2c7;
 
 pub struct BunnyFont<T> {
     texture: T,
@@ -51,6 +54,10 @@ where
         (texture_width / self.char_width) * (texture_height / self.char_height)
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     //gets coordinates of a character in a rectangle in (x, y, w, h) format
     pub fn get_src_uvs(&self, index: usize) -> (f32, f32, f32, f32) {
         let (texture_width, texture_height) = self.texture.get_pixel_dimensions();
@@ -102,48 +109,281 @@ where
     pub fn highest_char_index(&self) -> usize {
         self.total_char_indices() - 1
     }
+
+    //Converts a pixel position to the cell under it, for a grid drawn at the given scaling with its top left corner at origin
+    //Returns None if the position is outside of the grid
+    pub fn pick_cell(
+        &self,
+        point: (f32, f32),
+        scaling: f32,
+        origin: (f32, f32),
+        grid_dimensions: (usize, usize),
+    ) -> Option<(usize, usize)> {
+        let (x, y) = (point.0 - origin.0, point.1 - origin.1);
+        let (tile_width, tile_height) = (
+            scaling * self.char_width as f32,
+            scaling * self.char_height as f32,
+        );
+
+        //Checked before converting, as converting rounds positions just left of or above the grid into it
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+
+        let (cell_x, cell_y) = ((x / tile_width) as usize, (y / tile_height) as usize);
+        let (grid_width, grid_height) = grid_dimensions;
+
+        if cell_x < grid_width && cell_y < grid_height {
+            Some((cell_x, cell_y))
+        } else {
+            None
+        }
+    }
 }
 
-impl <T> BunnyFont<T> 
-    where T: SourceImage + PixelIndexable<T::Color>,
-        T::Color: Lerpable + IntoScalar
+impl<T> BunnyFont<T>
+where
+    T: SourceImage + PixelIndexable<T::Color>,
+    T::Color: IntoScalar,
 {
-    pub fn char_pixel(&self, bunny_char: &BunnyChar<T::Color>, x: usize, y: usize) -> T::Color {
+    //The pixel of the texture that ends up at a pixel of a drawn char
+    //Chars are drawn rotated clockwise and then mirrored, so this undoes the mirroring first and the rotation second
+    pub fn source_pixel_pos(&self, bunny_char: &BunnyChar<T::Color>, x: usize, y: usize) -> (usize, usize) {
         let (char_x, char_y) = self.get_char_pos_from_index(bunny_char.index);
         let (char_width, char_height) = self.char_dimensions();
 
         assert!(x < char_width);
         assert!(y < char_height);
 
-        let (rot_cw, rot_ch) = (char_width - 1, char_height - 1);
-        let (x_inv, y_inv) = (rot_cw - x, rot_ch - y);
+        let (x, y) = match bunny_char.mirror {
+            CharMirror::None => (x, y),
+            CharMirror::MirrorX => (char_width - 1 - x, y),
+            CharMirror::MirrorY => (x, char_height - 1 - y),
+            CharMirror::MirrorBoth => (char_width - 1 - x, char_height - 1 - y),
+        };
 
         let (x, y) = match bunny_char.rotation {
             CharRotation::None => (x, y),
-            CharRotation::Rotation90 => (y_inv, x),
-            CharRotation::Rotation180 => (x_inv, y_inv),
-            CharRotation::Rotation270 => (y, x_inv),
-        };
-
-        let (x, y) = match bunny_char.mirror {
-            CharMirror::None => (x, y),
-            CharMirror::MirrorX => (x_inv, y),
-            CharMirror::MirrorY => (x, y_inv),
-            CharMirror::MirrorBoth => (x_inv, y_inv),
+            CharRotation::Rotation90 => (y, char_width - 1 - x),
+            CharRotation::Rotation180 => (char_width - 1 - x, char_height - 1 - y),
+            CharRotation::Rotation270 => (char_height - 1 - y, x),
         };
 
         assert!(x < char_width && y < char_height, "char coordinates are out of bounds for char, this may be caused by rotating a non-square char");
 
-        let (char_pix_x, char_pix_y) = (x + char_x * char_width, y + char_y * char_height);
+        (x + char_x * char_width, y + char_y * char_height)
+    }
+
+    //How much of a pixel of a drawn char is covered by its glyph, from 0.0 to 1.0
+    pub fn coverage(&self, bunny_char: &BunnyChar<T::Color>, x: usize, y: usize) -> f32 {
+        let (pixel_x, pixel_y) = self.source_pixel_pos(bunny_char, x, y);
+
+        self.texture.get_pixel_at(pixel_x, pixel_y).into_scalar()
+    }
+
+    //Whether a pixel position lands on the inked part of a char drawn at the given scaling with its top left corner at origin
+    //Pixels count as inked when their coverage is at least the threshold
+    pub fn hit_test(
+        &self,
+        bunny_char: &BunnyChar<T::Color>,
+        point: (f32, f32),
+        origin: (f32, f32),
+        scaling: f32,
+        threshold: f32,
+    ) -> bool {
+        let (char_width, char_height) = self.char_dimensions();
+        let (x, y) = ((point.0 - origin.0) / scaling, (point.1 - origin.1) / scaling);
+
+        if x < 0.0 || y < 0.0 || x >= char_width as f32 || y >= char_height as f32 {
+            return false;
+        }
+
+        self.coverage(bunny_char, x as usize, y as usize) >= threshold
+    }
 
-        let texture_pixel = self.texture.get_pixel_at(char_pix_x, char_pix_y);
+    //Finds the cell of a grid drawn at origin whose glyph is inked under a pixel position
+    //Background colours don't count, so only the glyphs themselves can be hit
+    pub fn hit_test_grid(
+        &self,
+        grid: &BunnyGrid<T::Color>,
+        point: (f32, f32),
+        origin: (f32, f32),
+        scaling: f32,
+        threshold: f32,
+    ) -> Option<(usize, usize)>
+    where
+        T::Color: Color,
+    {
+        let (x, y) = self.pick_cell(point, scaling, origin, grid.dimensions())?;
+        let bunny_char = grid.get(x, y)?;
+
+        let (char_width, char_height) = self.char_dimensions();
+        let cell_origin = (
+            origin.0 + (x * char_width) as f32 * scaling,
+            origin.1 + (y * char_height) as f32 * scaling,
+        );
+
+        if self.hit_test(bunny_char, point, cell_origin, scaling, threshold) {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> BunnyFont<T>
+where
+    T: SourceImage + PixelIndexable<T::Color>,
+    T::Color: Lerpable + IntoScalar,
+{
+    pub fn char_pixel(&self, bunny_char: &BunnyChar<T::Color>, x: usize, y: usize) -> T::Color {
+        let (pixel_x, pixel_y) = self.source_pixel_pos(bunny_char, x, y);
+
+        let texture_pixel = self.texture.get_pixel_at(pixel_x, pixel_y);
         let scalar = texture_pixel.into_scalar();
         let foreground = &bunny_char.foreground;
 
         if let Some(background) = &bunny_char.background {
             Lerpable::lerp(background, foreground, scalar)
         } else {
-            Lerpable::lerp(texture_pixel, &foreground, scalar)
+            Lerpable::lerp(texture_pixel, foreground, scalar)
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::source_image::TestImage;
+
+    fn font() -> BunnyFont<TestImage> {
+        BunnyFont::new(TestImage((128, 64)), (8, 8))
+    }
+
+    #[test]
+    fn charset_positions_and_indices() {
+        let font = font();
+
+        assert_eq!(font.charset_dimensions(), (16, 8));
+        assert_eq!(font.len(), 128);
+        assert_eq!(font.get_char_pos_from_index(17), (1, 1));
+        assert_eq!(font.get_index_from_char_pos(1, 1), 17);
+        assert_eq!(font.get_src_uvs(17), (0.0625, 0.125, 0.0625, 0.125));
+    }
+
+    #[test]
+    fn pick_cell_accounts_for_scaling_and_origin() {
+        let font = font();
+
+        assert_eq!(
+            font.pick_cell((17.0, 9.0), 2.0, (1.0, 1.0), (4, 4)),
+            Some((1, 0))
+        );
+        assert_eq!(
+            font.pick_cell((64.9, 64.9), 2.0, (1.0, 1.0), (4, 4)),
+            Some((3, 3))
+        );
+        assert_eq!(font.pick_cell((65.0, 9.0), 2.0, (1.0, 1.0), (4, 4)), None);
+        assert_eq!(font.pick_cell((0.9, 9.0), 2.0, (1.0, 1.0), (4, 4)), None);
+    }
+
+    #[cfg(feature = "image-integration")]
+    mod image {
+        use ::image::{Rgba, RgbaImage};
+
+        use super::*;
+        use crate::char_transforms::orientation_matrix;
+
+        const INK: Rgba<u8> = Rgba([255; 4]);
+        const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+        const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+        //Two 4x4 chars, the second inked at only (1, 0)
+        fn font() -> BunnyFont<RgbaImage> {
+            let mut texture = RgbaImage::new(8, 4);
+            texture.put_pixel(5, 0, INK);
+
+            BunnyFont::new(texture, (4, 4))
+        }
+
+        fn oriented(rotation: CharRotation, mirror: CharMirror) -> BunnyChar<Rgba<u8>> {
+            BunnyChar::new(1, RED, Some(BLUE), rotation, mirror)
+        }
+
+        #[test]
+        fn source_pixels_follow_the_orientation_matrix() {
+            let font = font();
+
+            for rotation in [
+                CharRotation::None,
+                CharRotation::Rotation90,
+                CharRotation::Rotation180,
+                CharRotation::Rotation270,
+            ] {
+                for mirror in [
+                    CharMirror::None,
+                    CharMirror::MirrorX,
+                    CharMirror::MirrorY,
+                    CharMirror::MirrorBoth,
+                ] {
+                    let matrix = orientation_matrix(rotation, mirror);
+
+                    for (x, y) in (0..4).flat_map(|y| (0..4).map(move |x| (x, y))) {
+                        //Pixel centres relative to the centre of the char, doubled to keep them whole
+                        let (source_x, source_y) = (2 * x as i32 - 3, 2 * y as i32 - 3);
+                        let drawn_x = matrix[0][0] * source_x + matrix[0][1] * source_y;
+                        let drawn_y = matrix[1][0] * source_x + matrix[1][1] * source_y;
+
+                        assert_eq!(
+                            font.source_pixel_pos(
+                                &oriented(rotation, mirror),
+                                ((drawn_x + 3) / 2) as usize,
+                                ((drawn_y + 3) / 2) as usize,
+                            ),
+                            (x + 4, y),
+                            "{:?} {:?}",
+                            rotation,
+                            mirror
+                        );
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn hit_testing_only_counts_inked_pixels() {
+            let font = font();
+            let bunny_char = oriented(CharRotation::Rotation90, CharMirror::None);
+
+            assert!(font.hit_test(&bunny_char, (17.0, 13.0), (10.0, 10.0), 2.0, 0.5));
+            assert!(!font.hit_test(&bunny_char, (11.0, 11.0), (10.0, 10.0), 2.0, 0.5));
+            assert!(!font.hit_test(&bunny_char, (9.0, 13.0), (10.0, 10.0), 2.0, 0.5));
+
+            let mut grid = BunnyGrid::new((2, 1));
+            grid.set(1, 0, Some(oriented(CharRotation::None, CharMirror::None)));
+
+            assert_eq!(
+                font.hit_test_grid(&grid, (5.5, 0.5), (0.0, 0.0), 1.0, 0.5),
+                Some((1, 0))
+            );
+            assert_eq!(
+                font.hit_test_grid(&grid, (6.5, 0.5), (0.0, 0.0), 1.0, 0.5),
+                None
+            );
+            assert_eq!(
+                font.hit_test_grid(&grid, (1.5, 0.5), (0.0, 0.0), 1.0, 0.5),
+                None
+            );
+        }
+
+        #[test]
+        fn char_pixels_blend_the_background_into_the_foreground() {
+            let font = font();
+            let bunny_char = oriented(CharRotation::None, CharMirror::None);
+
+            assert_eq!(font.char_pixel(&bunny_char, 0, 0), BLUE);
+            let inked = font.char_pixel(&bunny_char, 1, 0);
+            assert_eq!((inked.0[0], inked.0[2]), (254, 0));
+        }
+    }
+}
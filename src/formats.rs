@@ -0,0 +1,129 @@
+#[cfg(feature = "image-integration")]
+pub mod animation;
+pub mod ansi;
+#[cfg(feature = "asciicast")]
+pub mod asciicast;
+pub mod delta;
+pub mod html;
+#[cfg(feature = "image-integration")]
+pub mod kitty;
+#[cfg(feature = "rexpaint")]
+pub mod rexpaint;
+#[cfg(feature = "image-integration")]
+pub mod sixel;
+pub mod svg;
+#[cfg(feature = "image-integration")]
+pub mod xbin;
+
+use std::{error::Error, io};
+
+pub(crate) fn invalid_data<E>(message: E) -> io::Error
+where
+    E: Into<Box<dyn Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+//Reads the parts of a file or packet that is already in memory, running out of bytes is an UnexpectedEof
+pub(crate) struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    pub(crate) fn take(&mut self, length: usize) -> io::Result<&'a [u8]> {
+        let bytes = self.bytes[self.position..]
+            .get(..length)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+
+        self.position += length;
+        Ok(bytes)
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    pub(crate) fn read_u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    //Only XBin files have 16 bit numbers
+    #[cfg(feature = "image-integration")]
+    pub(crate) fn read_u16(&mut self) -> io::Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    //Reads an unsigned LEB128 varint
+    pub(crate) fn read_varint(&mut self) -> io::Result<usize> {
+        let mut value: usize = 0;
+
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte = self.read_u8()?;
+            let bits = (byte & 0x7f) as usize;
+
+            //Bits that don't fit a usize mean the data is corrupt rather than just large
+            if bits << shift >> shift != bits {
+                return Err(invalid_data("varint is too large"));
+            }
+
+            value |= bits << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(invalid_data("varint is too large"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_readers() {
+        let mut reader = ByteReader::new(&[1, 2, 0xac, 0x02, 0x7f, 9]);
+
+        assert_eq!(reader.take(2).unwrap(), &[1, 2]);
+        assert_eq!(reader.read_varint().unwrap(), 300);
+        assert_eq!(reader.read_varint().unwrap(), 0x7f);
+        assert_eq!(reader.remaining(), 1);
+        assert_eq!(
+            reader.take(2).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(reader.read_u8().unwrap(), 9);
+        assert_eq!(
+            reader.read_u8().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn oversized_varints_are_invalid_data() {
+        let mut reader = ByteReader::new(&[0xff; 11]);
+        assert_eq!(
+            reader.read_varint().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        let mut reader = ByteReader::new(&[0x80, 0x80]);
+        assert_eq!(
+            reader.read_varint().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[cfg(feature = "image-integration")]
+    #[test]
+    fn u16s_are_little_endian() {
+        assert_eq!(ByteReader::new(&[0x34, 0x12]).read_u16().unwrap(), 0x1234);
+    }
+}
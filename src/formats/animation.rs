@@ -0,0 +1,340 @@
+#[cfg(feature = "apng")]
+use std::io;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    imageops, Delay, Frame, ImageResult, Rgba, RgbaImage,
+};
+
+use crate::integrations::image::{ImageBunnyFont, ImageBunnyGrid};
+
+//Records grids as frames of an animation, each shown for its own delay
+#[derive(Clone, Debug, Default)]
+pub struct GridRecorder {
+    frames: Vec<(ImageBunnyGrid, Duration)>,
+}
+
+impl GridRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, grid: &ImageBunnyGrid, delay: Duration) {
+        self.frames.push((grid.clone(), delay));
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    pub fn frames(&self) -> impl Iterator<Item = (&ImageBunnyGrid, Duration)> {
+        self.frames.iter().map(|(grid, delay)| (grid, *delay))
+    }
+
+    //The colours the recorded cells are drawn with, or None if there are too many to fit a GIF palette
+    fn used_colors(&self) -> Option<Vec<Rgba<u8>>> {
+        let mut colors: Vec<Rgba<u8>> = Vec::new();
+
+        for (grid, _) in &self.frames {
+            for (_, cell) in grid.cells() {
+                let bunny_char = match cell {
+                    Some(bunny_char) => bunny_char,
+                    None => continue,
+                };
+
+                for color in std::iter::once(bunny_char.foreground).chain(bunny_char.background) {
+                    let opaque = Rgba([color.0[0], color.0[1], color.0[2], 255]);
+
+                    if !colors.contains(&opaque) {
+                        colors.push(opaque);
+                    }
+                }
+            }
+        }
+
+        //One palette entry is kept for transparency
+        if colors.len() <= 255 {
+            Some(colors)
+        } else {
+            None
+        }
+    }
+
+    //Renders every frame at the given scale, frames are padded with transparency to the size of the largest
+    pub fn render_frames(&self, font: &ImageBunnyFont, scale: u32) -> Vec<(RgbaImage, Duration)> {
+        let rendered: Vec<(RgbaImage, Duration)> = self
+            .frames
+            .iter()
+            .map(|(grid, delay)| (font.render_grid(grid, scale), *delay))
+            .collect();
+
+        let (width, height) = rendered.iter().fold((0, 0), |(width, height), (image, _)| {
+            (width.max(image.width()), height.max(image.height()))
+        });
+
+        rendered
+            .into_iter()
+            .map(|(image, delay)| {
+                if image.dimensions() == (width, height) {
+                    return (image, delay);
+                }
+
+                let mut padded = RgbaImage::new(width, height);
+                imageops::replace(&mut padded, &image, 0, 0);
+                (padded, delay)
+            })
+            .collect()
+    }
+
+    //Encodes the frames as a looping GIF
+    //When the cells use few enough colours every pixel is snapped to one of them, so the palette is exactly the colours used
+    pub fn write_gif<W>(&self, writer: W, font: &ImageBunnyFont, scale: u32) -> ImageResult<()>
+    where
+        W: Write,
+    {
+        let palette = self.used_colors();
+
+        let frames = self
+            .render_frames(font, scale)
+            .into_iter()
+            .map(|(mut image, delay)| {
+                if let Some(palette) = &palette {
+                    snap_to_palette(&mut image, palette);
+                }
+
+                Frame::from_parts(image, 0, 0, Delay::from_saturating_duration(delay))
+            });
+
+        let mut encoder = GifEncoder::new(writer);
+        encoder.set_repeat(Repeat::Infinite)?;
+        encoder.encode_frames(frames)
+    }
+
+    pub fn save_gif<P>(&self, path: P, font: &ImageBunnyFont, scale: u32) -> ImageResult<()>
+    where
+        P: AsRef<Path>,
+    {
+        self.write_gif(BufWriter::new(File::create(path)?), font, scale)
+    }
+
+    //Encodes the frames as a looping APNG, keeping every colour and level of transparency exactly
+    #[cfg(feature = "apng")]
+    pub fn write_apng<W>(&self, writer: W, font: &ImageBunnyFont, scale: u32) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let frames = self.render_frames(font, scale);
+
+        let (width, height) = match frames.first() {
+            Some((image, _)) => image.dimensions(),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "no frames to encode",
+                ))
+            }
+        };
+
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(frames.len() as u32, 0)?;
+
+        let mut writer = encoder.write_header()?;
+
+        for (image, delay) in &frames {
+            //Delays are written in milliseconds
+            let delay = delay.as_millis().min(u16::MAX as u128) as u16;
+
+            writer.set_frame_delay(delay, 1000)?;
+            writer.write_image_data(image.as_raw())?;
+        }
+
+        writer.finish()?;
+        Ok(())
+    }
+
+    #[cfg(feature = "apng")]
+    pub fn save_apng<P>(&self, path: P, font: &ImageBunnyFont, scale: u32) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_apng(&mut writer, font, scale)?;
+        writer.flush()
+    }
+}
+
+//Snaps every pixel to the closest palette colour, with mostly transparent pixels made fully transparent
+fn snap_to_palette(image: &mut RgbaImage, palette: &[Rgba<u8>]) {
+    let distance = |a: &Rgba<u8>, b: &Rgba<u8>| {
+        (0..3)
+            .map(|index| (a.0[index] as i32 - b.0[index] as i32).pow(2))
+            .sum::<i32>()
+    };
+
+    for pixel in image.pixels_mut() {
+        *pixel = if pixel.0[3] < 128 {
+            Rgba([0; 4])
+        } else {
+            palette
+                .iter()
+                .min_by_key(|color| distance(color, pixel))
+                .copied()
+                .unwrap_or(*pixel)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{codecs::gif::GifDecoder, AnimationDecoder};
+
+    use super::*;
+    use crate::{
+        char::BunnyChar,
+        char_transforms::{CharMirror, CharRotation},
+        grid::BunnyGrid,
+    };
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+    const CLEAR: Rgba<u8> = Rgba([0; 4]);
+
+    //Two 2x2 chars, the second inked at only its top left pixel
+    fn font() -> ImageBunnyFont {
+        let mut texture = RgbaImage::new(4, 2);
+        texture.put_pixel(2, 0, Rgba([255; 4]));
+
+        ImageBunnyFont::new(texture, (2, 2))
+    }
+
+    fn grid(width: usize) -> ImageBunnyGrid {
+        let mut grid = BunnyGrid::new((width, 1));
+        grid.set(
+            0,
+            0,
+            Some(BunnyChar::new(
+                1,
+                RED,
+                Some(BLUE),
+                CharRotation::None,
+                CharMirror::None,
+            )),
+        );
+        grid
+    }
+
+    fn recorder() -> GridRecorder {
+        let mut recorder = GridRecorder::new();
+        recorder.push(&grid(1), Duration::from_millis(100));
+        recorder.push(&grid(2), Duration::from_millis(250));
+        recorder
+    }
+
+    #[test]
+    fn frames_are_padded_to_the_largest() {
+        let frames = recorder().render_frames(&font(), 1);
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].0.dimensions(), (4, 2));
+        assert_eq!(frames[0].0.get_pixel(0, 0), &RED);
+        assert_eq!(frames[0].0.get_pixel(2, 0), &CLEAR);
+        assert_eq!(frames[1].1, Duration::from_millis(250));
+    }
+
+    #[test]
+    fn palettes_hold_the_colours_used() {
+        assert_eq!(recorder().used_colors(), Some(vec![RED, BLUE]));
+
+        let mut colorful = BunnyGrid::new((256, 1));
+
+        for x in 0..256 {
+            colorful.set(
+                x,
+                0,
+                grid(1)
+                    .get(0, 0)
+                    .map(|cell| cell.foreground(Rgba([x as u8, 1, 2, 255]))),
+            );
+        }
+
+        let mut recorder = GridRecorder::new();
+        recorder.push(&colorful, Duration::from_millis(100));
+        assert_eq!(recorder.used_colors(), None);
+
+        let mut image = RgbaImage::from_pixel(2, 1, Rgba([250, 5, 5, 255]));
+        image.put_pixel(1, 0, Rgba([255, 0, 0, 100]));
+        snap_to_palette(&mut image, &[RED, BLUE]);
+
+        assert_eq!(
+            image.pixels().copied().collect::<Vec<_>>(),
+            vec![RED, CLEAR]
+        );
+    }
+
+    #[test]
+    fn gifs_round_trip() {
+        let mut gif = Vec::new();
+        recorder().write_gif(&mut gif, &font(), 1).unwrap();
+
+        let frames = GifDecoder::new(gif.as_slice())
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(
+            frames[1].delay(),
+            Delay::from_saturating_duration(Duration::from_millis(250))
+        );
+        assert_eq!(frames[0].buffer().get_pixel(0, 0), &RED);
+        assert_eq!(frames[0].buffer().get_pixel(1, 0), &BLUE);
+        assert_eq!(frames[0].buffer().get_pixel(2, 0).0[3], 0);
+    }
+
+    #[cfg(feature = "apng")]
+    #[test]
+    fn apngs_round_trip() {
+        let mut apng = Vec::new();
+        recorder().write_apng(&mut apng, &font(), 1).unwrap();
+
+        let mut reader = png::Decoder::new(apng.as_slice()).read_info().unwrap();
+        let control = reader.info().animation_control().unwrap();
+
+        assert_eq!((control.num_frames, control.num_plays), (2, 0));
+
+        let frames = recorder().render_frames(&font(), 1);
+        let mut buffer = vec![0; reader.output_buffer_size()];
+
+        for (image, delay) in frames {
+            reader.next_frame(&mut buffer).unwrap();
+            let frame = reader.info().frame_control().unwrap();
+
+            assert_eq!(buffer, image.into_raw());
+            assert_eq!(
+                (frame.delay_num, frame.delay_den),
+                (delay.as_millis() as u16, 1000)
+            );
+        }
+
+        assert!(GridRecorder::new()
+            .write_apng(Vec::new(), &font(), 1)
+            .is_err());
+    }
+}
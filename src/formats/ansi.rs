@@ -0,0 +1,466 @@
+use std::{fs, io, path::Path};
+
+use crate::{
+    char::BunnyChar,
+    char_transforms::{CharMirror, CharRotation},
+    charmap::{cp437_code, oriented_char, CharMap},
+    grid::BunnyGrid,
+    traits::color::Color,
+};
+
+//The 16 colours of the VGA text mode palette, in SGR order with the bright colours last
+pub const VGA_PALETTE: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [170, 0, 0],
+    [0, 170, 0],
+    [170, 85, 0],
+    [0, 0, 170],
+    [170, 0, 170],
+    [0, 170, 170],
+    [170, 170, 170],
+    [85, 85, 85],
+    [255, 85, 85],
+    [85, 255, 85],
+    [255, 255, 85],
+    [85, 85, 255],
+    [255, 85, 255],
+    [85, 255, 255],
+    [255, 255, 255],
+];
+
+//Codes that ANSI viewers act on instead of drawing, such as line breaks, escape and end of file
+const CONTROL_CODES: [u8; 6] = [0x07, 0x08, 0x0a, 0x0d, 0x1a, 0x1b];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AnsiColorMode {
+    //The VGA palette, with bold for bright foregrounds and blink for bright backgrounds as iCE colours
+    Ansi16,
+    //The xterm 256 colour palette
+    Ansi256,
+    //24-bit colour
+    TrueColor,
+}
+
+//What to draw in place of a rotated or mirrored glyph that has no code page 437 equivalent
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OrientationFallback {
+    //The glyph drawn upright
+    Upright,
+    Char(char),
+}
+
+//The metadata of a SAUCE record, text is cut off at the lengths SAUCE allows
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct SauceInfo {
+    pub title: String,
+    pub author: String,
+    pub group: String,
+    //In CCYYMMDD form
+    pub date: String,
+    //The font the art is meant to be viewed with, such as "IBM VGA"
+    pub font: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnsiOptions {
+    pub color_mode: AnsiColorMode,
+    //The char written for glyphs that have no code page 437 code
+    pub fallback: char,
+    pub orientation_fallback: OrientationFallback,
+    pub sauce: Option<SauceInfo>,
+}
+
+impl Default for AnsiOptions {
+    fn default() -> Self {
+        Self {
+            color_mode: AnsiColorMode::Ansi16,
+            fallback: '?',
+            orientation_fallback: OrientationFallback::Upright,
+            sauce: None,
+        }
+    }
+}
+
+impl AnsiOptions {
+    pub fn color_mode(self, color_mode: AnsiColorMode) -> Self {
+        Self { color_mode, ..self }
+    }
+
+    pub fn fallback(self, fallback: char) -> Self {
+        Self { fallback, ..self }
+    }
+
+    pub fn orientation_fallback(self, orientation_fallback: OrientationFallback) -> Self {
+        Self {
+            orientation_fallback,
+            ..self
+        }
+    }
+
+    pub fn sauce(self, sauce: Option<SauceInfo>) -> Self {
+        Self { sauce, ..self }
+    }
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> i32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| (*a as i32 - *b as i32).pow(2))
+        .sum()
+}
+
+//The index of the closest colour in a palette
+pub fn nearest_color(palette: &[[u8; 3]], rgb: [u8; 3]) -> usize {
+    (0..palette.len())
+        .min_by_key(|index| distance(palette[*index], rgb))
+        .unwrap_or(0)
+}
+
+//The closest colour of the xterm 256 colour palette, leaving out the first 16 colours which terminals define themselves
+pub fn nearest_xterm_color(rgb: [u8; 3]) -> u8 {
+    (16..=255)
+        .min_by_key(|index| distance(xterm_color(*index), rgb))
+        .unwrap_or(16)
+}
+
+//The colour of an entry of the xterm 256 colour palette
+pub fn xterm_color(index: u8) -> [u8; 3] {
+    const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    match index {
+        0..=15 => VGA_PALETTE[index as usize],
+        16..=231 => {
+            let index = index as usize - 16;
+            [
+                CUBE_LEVELS[index / 36],
+                CUBE_LEVELS[index / 6 % 6],
+                CUBE_LEVELS[index % 6],
+            ]
+        }
+        _ => {
+            let level = 8 + (index - 232) * 10;
+            [level, level, level]
+        }
+    }
+}
+
+fn rgb<C>(color: C) -> [u8; 3]
+where
+    C: Color,
+{
+    let [r, g, b, _] = color.to_rgba8();
+    [r, g, b]
+}
+
+//The code page 437 code a cell is written as
+fn cell_code<C>(bunny_char: &BunnyChar<C>, charmap: &CharMap, options: &AnsiOptions) -> u8 {
+    let fallback = cp437_code(options.fallback).unwrap_or(b'?');
+
+    let ch = match charmap.char_of(bunny_char.index) {
+        Some(ch) => ch,
+        None => return fallback,
+    };
+
+    let upright =
+        (bunny_char.rotation, bunny_char.mirror) == (CharRotation::None, CharMirror::None);
+
+    let ch = match oriented_char(ch, bunny_char.rotation, bunny_char.mirror) {
+        Some(oriented) => oriented,
+        None if upright => ch,
+        None => match options.orientation_fallback {
+            OrientationFallback::Upright => ch,
+            OrientationFallback::Char(fallback) => fallback,
+        },
+    };
+
+    cp437_code(ch)
+        .filter(|code| !CONTROL_CODES.contains(code))
+        .unwrap_or(fallback)
+}
+
+//The SGR parameters setting a cell's colours, after a reset
+fn sgr_parameters<C>(bunny_char: &BunnyChar<C>, color_mode: AnsiColorMode) -> String
+where
+    C: Color,
+{
+    let foreground = rgb(bunny_char.foreground);
+    let background = bunny_char.background.map(rgb);
+
+    match color_mode {
+        AnsiColorMode::Ansi16 => {
+            let foreground = nearest_color(&VGA_PALETTE, foreground);
+            let mut parameters = format!(
+                "{}3{}",
+                if foreground > 7 { "1;" } else { "" },
+                foreground % 8
+            );
+
+            if let Some(background) = background {
+                let background = nearest_color(&VGA_PALETTE, background);
+                parameters += &format!(
+                    ";{}4{}",
+                    if background > 7 { "5;" } else { "" },
+                    background % 8
+                );
+            }
+
+            parameters
+        }
+        AnsiColorMode::Ansi256 => {
+            let mut parameters = format!("38;5;{}", nearest_xterm_color(foreground));
+
+            if let Some(background) = background {
+                parameters += &format!(";48;5;{}", nearest_xterm_color(background));
+            }
+
+            parameters
+        }
+        AnsiColorMode::TrueColor => {
+            let [r, g, b] = foreground;
+            let mut parameters = format!("38;2;{};{};{}", r, g, b);
+
+            if let Some([r, g, b]) = background {
+                parameters += &format!(";48;2;{};{};{}", r, g, b);
+            }
+
+            parameters
+        }
+    }
+}
+
+//Writes a grid as ANSI art, with a SAUCE record if the options have one
+pub fn export_ansi<C>(grid: &BunnyGrid<C>, charmap: &CharMap, options: &AnsiOptions) -> Vec<u8>
+where
+    C: Color,
+{
+    let (width, height) = grid.dimensions();
+    let mut bytes = b"\x1b[0m".to_vec();
+    let mut ice_colors = false;
+
+    for y in 0..height {
+        //Colours are only written when they change, empty cells are written as spaces with the default colours
+        let mut current = None;
+
+        for x in 0..width {
+            let (parameters, code) = match grid.get(x, y) {
+                Some(bunny_char) => {
+                    //Bright backgrounds need viewers to treat blink as iCE colours
+                    ice_colors |= options.color_mode == AnsiColorMode::Ansi16
+                        && bunny_char.background.is_some_and(|background| {
+                            nearest_color(&VGA_PALETTE, rgb(background)) > 7
+                        });
+
+                    (
+                        Some(sgr_parameters(bunny_char, options.color_mode)),
+                        cell_code(bunny_char, charmap, options),
+                    )
+                }
+                None => (None, b' '),
+            };
+
+            if parameters != current {
+                match &parameters {
+                    Some(parameters) => bytes.extend(format!("\x1b[0;{}m", parameters).bytes()),
+                    None => bytes.extend(b"\x1b[0m"),
+                }
+
+                current = parameters;
+            }
+
+            bytes.push(code);
+        }
+
+        bytes.extend(b"\x1b[0m\r\n");
+    }
+
+    if let Some(sauce) = &options.sauce {
+        let file_size = bytes.len() as u32;
+
+        bytes.push(0x1a);
+        bytes.extend(sauce_record(sauce, file_size, (width, height), ice_colors));
+    }
+
+    bytes
+}
+
+pub fn save_ansi<P, C>(
+    path: P,
+    grid: &BunnyGrid<C>,
+    charmap: &CharMap,
+    options: &AnsiOptions,
+) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    C: Color,
+{
+    fs::write(path, export_ansi(grid, charmap, options))
+}
+
+//A 128 byte SAUCE record describing a character based ANSi file
+fn sauce_record(
+    sauce: &SauceInfo,
+    file_size: u32,
+    dimensions: (usize, usize),
+    ice_colors: bool,
+) -> Vec<u8> {
+    //Text fields are padded with spaces, apart from the font name which is padded with zeroes
+    let field = |text: &str, length: usize, padding: u8| {
+        let mut field: Vec<u8> = text
+            .chars()
+            .map(|ch| cp437_code(ch).unwrap_or(b'?'))
+            .take(length)
+            .collect();
+
+        field.resize(length, padding);
+        field
+    };
+
+    let (width, height) = dimensions;
+
+    let mut record = b"SAUCE00".to_vec();
+    record.extend(field(&sauce.title, 35, b' '));
+    record.extend(field(&sauce.author, 20, b' '));
+    record.extend(field(&sauce.group, 20, b' '));
+    record.extend(field(&sauce.date, 8, b' '));
+    record.extend(file_size.to_le_bytes());
+    //Data type 1 is character art, file type 1 is ANSi
+    record.extend([1, 1]);
+    record.extend((width.min(u16::MAX as usize) as u16).to_le_bytes());
+    record.extend((height.min(u16::MAX as usize) as u16).to_le_bytes());
+    record.extend([0; 4]);
+    //No comment block, and the flags only say whether blink means bright backgrounds
+    record.push(0);
+    record.push(ice_colors as u8);
+    record.extend(field(&sauce.font, 22, 0));
+
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::color::TestColor;
+
+    fn cell(ch: char, foreground: [u8; 3], background: Option<[u8; 3]>) -> BunnyChar<TestColor> {
+        let color = |[r, g, b]: [u8; 3]| TestColor([r, g, b, 255]);
+
+        BunnyChar::new(
+            CharMap::cp437().index_of(ch).unwrap(),
+            color(foreground),
+            background.map(color),
+            CharRotation::None,
+            CharMirror::None,
+        )
+    }
+
+    fn export(cells: &[Option<BunnyChar<TestColor>>], options: &AnsiOptions) -> Vec<u8> {
+        let mut grid = BunnyGrid::new((cells.len(), 1));
+
+        for (x, cell) in cells.iter().enumerate() {
+            grid.set(x, 0, *cell);
+        }
+
+        export_ansi(&grid, &CharMap::cp437(), options)
+    }
+
+    #[test]
+    fn colours_are_only_written_when_they_change() {
+        let red = cell('A', [170, 0, 0], None);
+
+        assert_eq!(
+            export(&[Some(red), Some(red), None], &AnsiOptions::default()),
+            b"\x1b[0m\x1b[0;31mAA\x1b[0m \x1b[0m\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn colour_modes() {
+        let bright = [Some(cell('#', [255, 85, 85], Some([85, 85, 255])))];
+
+        let sgr = |color_mode| {
+            let bytes = export(&bright, &AnsiOptions::default().color_mode(color_mode));
+            String::from_utf8(bytes).unwrap()
+        };
+
+        assert!(sgr(AnsiColorMode::Ansi16).contains("\x1b[0;1;31;5;44m#"));
+        assert!(sgr(AnsiColorMode::Ansi256).contains("\x1b[0;38;5;203;48;5;63m#"));
+        assert!(sgr(AnsiColorMode::TrueColor).contains("\x1b[0;38;2;255;85;85;48;2;85;85;255m#"));
+    }
+
+    #[test]
+    fn xterm_palette() {
+        assert_eq!(xterm_color(1), [170, 0, 0]);
+        assert_eq!(xterm_color(16), [0, 0, 0]);
+        assert_eq!(xterm_color(196), [255, 0, 0]);
+        assert_eq!(xterm_color(231), [255, 255, 255]);
+        assert_eq!(xterm_color(232), [8, 8, 8]);
+        assert_eq!(xterm_color(255), [238, 238, 238]);
+
+        assert_eq!(nearest_xterm_color([250, 5, 5]), 196);
+        assert_eq!(nearest_color(&VGA_PALETTE, [250, 80, 80]), 9);
+    }
+
+    #[test]
+    fn cells_are_written_as_oriented_cp437_codes() {
+        let code = |bunny_char: BunnyChar<TestColor>, options: &AnsiOptions| {
+            cell_code(&bunny_char, &CharMap::cp437(), options)
+        };
+        let options = AnsiOptions::default();
+        let white = [255; 3];
+
+        let rotated = |ch| cell(ch, white, None).rotation(CharRotation::Rotation90);
+
+        assert_eq!(code(rotated('─'), &options), 0xb3);
+        assert_eq!(code(rotated('('), &options), b'(');
+        assert_eq!(
+            code(
+                rotated('('),
+                &options
+                    .clone()
+                    .orientation_fallback(OrientationFallback::Char('*'))
+            ),
+            b'*'
+        );
+
+        //The arrow at 0x1b would be read as an escape
+        assert_eq!(code(cell('←', white, None), &options), b'?');
+        assert_eq!(
+            code(cell('A', white, None).index(0x1000), &options.fallback('x')),
+            b'x'
+        );
+    }
+
+    #[test]
+    fn sauce_records_describe_the_art() {
+        let sauce = SauceInfo {
+            title: "Title".to_string(),
+            author: "Author".to_string(),
+            group: "Group".to_string(),
+            date: "20261019".to_string(),
+            font: "IBM VGA".to_string(),
+        };
+
+        let bytes = export(
+            &[
+                None,
+                Some(cell('A', [0, 0, 0], Some([255, 255, 255]))),
+                None,
+            ],
+            &AnsiOptions::default().sauce(Some(sauce)),
+        );
+
+        let record = &bytes[bytes.len() - 128..];
+        let art_length = bytes.len() - 129;
+
+        assert_eq!(bytes[art_length], 0x1a);
+        assert_eq!(&record[..7], b"SAUCE00");
+        assert_eq!(&record[7..12], b"Title");
+        assert_eq!(record[41], b' ');
+        assert_eq!(&record[82..90], b"20261019");
+        assert_eq!(&record[90..94], &(art_length as u32).to_le_bytes());
+        assert_eq!(&record[94..100], &[1, 1, 3, 0, 1, 0]);
+        assert_eq!(record[105], 1);
+        assert_eq!(&record[106..113], b"IBM VGA");
+        assert_eq!(record[113], 0);
+    }
+}
@@ -0,0 +1,498 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    path::Path,
+    time::Duration,
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    char::BunnyChar,
+    char_transforms::{CharMirror, CharRotation},
+    charmap::CharMap,
+    formats::{
+        ansi::{xterm_color, VGA_PALETTE},
+        invalid_data,
+    },
+    grid::BunnyGrid,
+    terminal::TerminalRenderer,
+    traits::color::Color,
+};
+
+//Asciicast v2 files are a line with a JSON header followed by a line per event
+//Output events are written as [seconds since the start, "o", text written to the terminal]
+
+//Text written to the terminal at a time since the start of the recording
+#[derive(Clone, Debug, PartialEq)]
+pub struct CastEvent {
+    pub time: Duration,
+    pub output: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Asciicast {
+    pub width: usize,
+    pub height: usize,
+    pub events: Vec<CastEvent>,
+}
+
+impl Asciicast {
+    pub fn new(dimensions: (usize, usize)) -> Self {
+        let (width, height) = dimensions;
+
+        Self {
+            width,
+            height,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn write<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        //Written by hand rather than with json!, which would sort the version after the dimensions
+        writeln!(
+            writer,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}}}",
+            self.width, self.height
+        )?;
+
+        for event in &self.events {
+            writeln!(
+                writer,
+                "{}",
+                json!([event.time.as_secs_f64(), "o", event.output])
+            )?;
+        }
+
+        writer.flush()
+    }
+
+    pub fn save<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+
+    //Reads the output events of a cast, events of other kinds such as input and markers are skipped
+    pub fn read<R>(reader: R) -> io::Result<Self>
+    where
+        R: Read,
+    {
+        let mut lines = BufReader::new(reader).lines();
+
+        let header: Value = match lines.next() {
+            Some(line) => serde_json::from_str(&line?)?,
+            None => return Err(invalid_data("missing asciicast header")),
+        };
+
+        if header["version"] != 2 {
+            return Err(invalid_data("unsupported asciicast version"));
+        }
+
+        let dimension = |key: &str| {
+            header[key]
+                .as_u64()
+                .map(|value| value as usize)
+                .ok_or_else(|| invalid_data("invalid asciicast dimensions"))
+        };
+
+        let mut cast = Self::new((dimension("width")?, dimension("height")?));
+
+        for line in lines {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let event: Value = serde_json::from_str(&line)?;
+
+            let (time, kind, output) = match event.as_array().map(Vec::as_slice) {
+                Some([time, kind, output]) => (time.as_f64(), kind.as_str(), output.as_str()),
+                _ => return Err(invalid_data("invalid asciicast event")),
+            };
+
+            match (time, kind, output) {
+                (Some(time), Some("o"), Some(output)) => cast.events.push(CastEvent {
+                    time: Duration::try_from_secs_f64(time)
+                        .map_err(|_| invalid_data("invalid asciicast event time"))?,
+                    output: output.to_string(),
+                }),
+                (Some(_), Some(_), _) => (),
+                _ => return Err(invalid_data("invalid asciicast event")),
+            }
+        }
+
+        Ok(cast)
+    }
+
+    pub fn load<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::read(File::open(path)?)
+    }
+
+    //Plays the cast back as the grid the terminal shows after each event, turning chars back into glyphs through the charmap
+    //Chars the charmap doesn't have and blank cells without a background become empty cells
+    //Rotated and mirrored glyphs that were written as other chars come back as those chars, upright
+    pub fn frames<C>(&self, charmap: &CharMap) -> Vec<(Duration, BunnyGrid<C>)>
+    where
+        C: Color,
+    {
+        let mut screen = Screen::new((self.width, self.height));
+
+        self.events
+            .iter()
+            .map(|event| {
+                screen.write(&event.output, charmap);
+                (event.time, screen.grid.clone())
+            })
+            .collect()
+    }
+}
+
+//Records grids drawn with a terminal renderer as a cast, so that each event only holds the cells that changed
+#[derive(Clone, Debug)]
+pub struct CastRecorder<C> {
+    renderer: TerminalRenderer<C>,
+    cast: Asciicast,
+}
+
+impl<C> CastRecorder<C>
+where
+    C: Color,
+{
+    pub fn new(renderer: TerminalRenderer<C>) -> Self {
+        Self {
+            renderer,
+            cast: Asciicast::new((0, 0)),
+        }
+    }
+
+    //Records a grid as shown at a time since the start, the cast grows to fit the largest grid recorded
+    //Grids that are the same as the last one recorded add no event
+    pub fn push(&mut self, grid: &BunnyGrid<C>, charmap: &CharMap, time: Duration) {
+        let mut output = Vec::new();
+
+        //Writing to a Vec can't fail
+        self.renderer
+            .draw(&mut output, grid, charmap)
+            .expect("failed to draw grid");
+
+        let (width, height) = grid.dimensions();
+        self.cast.width = self.cast.width.max(width);
+        self.cast.height = self.cast.height.max(height);
+
+        if !output.is_empty() {
+            self.cast.events.push(CastEvent {
+                time,
+                output: String::from_utf8_lossy(&output).into_owned(),
+            });
+        }
+    }
+
+    pub fn cast(&self) -> &Asciicast {
+        &self.cast
+    }
+
+    pub fn into_cast(self) -> Asciicast {
+        self.cast
+    }
+}
+
+//The colours text is currently written with, None being the terminal's default
+#[derive(Clone, Copy, Debug, Default)]
+struct Style {
+    foreground: Option<[u8; 3]>,
+    background: Option<[u8; 3]>,
+}
+
+//Just enough of a terminal to play back the output of a terminal renderer
+struct Screen<C> {
+    grid: BunnyGrid<C>,
+    cursor: (usize, usize),
+    style: Style,
+}
+
+impl<C> Screen<C>
+where
+    C: Color,
+{
+    fn new(dimensions: (usize, usize)) -> Self {
+        Self {
+            grid: BunnyGrid::new(dimensions),
+            cursor: (0, 0),
+            style: Style::default(),
+        }
+    }
+
+    fn write(&mut self, output: &str, charmap: &CharMap) {
+        let mut chars = output.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            if ch != '\x1b' {
+                self.put(ch, charmap);
+                continue;
+            }
+
+            //Only control sequences are understood, for other escapes the char after the escape is skipped
+            if chars.next() != Some('[') {
+                continue;
+            }
+
+            let mut parameters = String::new();
+
+            while let Some(&ch) = chars.peek() {
+                if !(ch.is_ascii_digit() || ch == ';' || ch == '?') {
+                    break;
+                }
+
+                parameters.push(ch);
+                chars.next();
+            }
+
+            match chars.next() {
+                Some('H') | Some('f') => self.move_cursor(&parameters),
+                Some('J') if parameters == "2" => {
+                    self.grid = BunnyGrid::new(self.grid.dimensions());
+                }
+                Some('m') => self.select_graphic_rendition(&parameters),
+                _ => (),
+            }
+        }
+    }
+
+    //Writes a char at the cursor, chars past the edge of the screen are dropped
+    fn put(&mut self, ch: char, charmap: &CharMap) {
+        let (x, y) = self.cursor;
+        let (width, height) = self.grid.dimensions();
+
+        if ch.is_control() {
+            return;
+        }
+
+        if x < width && y < height {
+            let style = self.style;
+            let color = |[r, g, b]: [u8; 3]| C::from_rgba8([r, g, b, 255]);
+
+            //Text without its own foreground is drawn in the terminal's usual light grey
+            let cell = if ch == ' ' && style.background.is_none() {
+                None
+            } else {
+                charmap.exact_index_of(ch).map(|index| {
+                    BunnyChar::new(
+                        index,
+                        color(style.foreground.unwrap_or(VGA_PALETTE[7])),
+                        style.background.map(color),
+                        CharRotation::None,
+                        CharMirror::None,
+                    )
+                })
+            };
+
+            self.grid.set(x, y, cell);
+        }
+
+        self.cursor = (x + 1, y);
+    }
+
+    //Positions are one based, with missing positions meaning the first row or column
+    fn move_cursor(&mut self, parameters: &str) {
+        let mut positions = parameters
+            .split(';')
+            .map(|position| position.parse::<usize>().unwrap_or(1).max(1) - 1);
+
+        let y = positions.next().unwrap_or(0);
+        let x = positions.next().unwrap_or(0);
+
+        self.cursor = (x, y);
+    }
+
+    fn select_graphic_rendition(&mut self, parameters: &str) {
+        let parameters: Vec<u32> = parameters
+            .split(';')
+            .map(|parameter| parameter.parse().unwrap_or(0))
+            .collect();
+
+        let mut parameters = parameters.iter().copied();
+
+        while let Some(parameter) = parameters.next() {
+            match parameter {
+                0 => self.style = Style::default(),
+                30..=37 => self.style.foreground = Some(VGA_PALETTE[parameter as usize - 30]),
+                90..=97 => self.style.foreground = Some(VGA_PALETTE[parameter as usize - 82]),
+                40..=47 => self.style.background = Some(VGA_PALETTE[parameter as usize - 40]),
+                100..=107 => self.style.background = Some(VGA_PALETTE[parameter as usize - 92]),
+                39 => self.style.foreground = None,
+                49 => self.style.background = None,
+                38 | 48 => {
+                    let color = match parameters.next() {
+                        Some(5) => parameters.next().map(|index| xterm_color(index as u8)),
+                        Some(2) => {
+                            let mut channel = || parameters.next().map(|channel| channel as u8);
+
+                            match (channel(), channel(), channel()) {
+                                (Some(r), Some(g), Some(b)) => Some([r, g, b]),
+                                _ => None,
+                            }
+                        }
+                        _ => None,
+                    };
+
+                    if parameter == 38 {
+                        self.style.foreground = color;
+                    } else {
+                        self.style.background = color;
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{formats::ansi::AnsiColorMode, traits::color::TestColor};
+
+    fn cells(grid: &BunnyGrid<TestColor>) -> Vec<Option<BunnyChar<TestColor>>> {
+        grid.cells().map(|(_, cell)| cell.copied()).collect()
+    }
+
+    #[test]
+    fn casts_round_trip() {
+        let mut cast = Asciicast::new((80, 24));
+        cast.events.push(CastEvent {
+            time: Duration::from_millis(1500),
+            output: "\x1b[1;1Hhi \"there\"\n".to_string(),
+        });
+
+        let mut bytes = Vec::new();
+        cast.write(&mut bytes).unwrap();
+
+        assert_eq!(
+            String::from_utf8(bytes.clone()).unwrap(),
+            "{\"version\": 2, \"width\": 80, \"height\": 24}\n[1.5,\"o\",\"\\u001b[1;1Hhi \\\"there\\\"\\n\"]\n"
+        );
+        assert_eq!(Asciicast::read(bytes.as_slice()).unwrap(), cast);
+    }
+
+    #[test]
+    fn other_events_are_skipped() {
+        let cast = Asciicast::read(
+            "{\"version\": 2, \"width\": 2, \"height\": 1}\n[0.5, \"i\", \"q\"]\n\n[1, \"o\", \"a\"]\n".as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            cast.events,
+            vec![CastEvent {
+                time: Duration::from_secs(1),
+                output: "a".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn invalid_casts() {
+        let read = |cast: &str| Asciicast::read(cast.as_bytes()).unwrap_err().kind();
+        let header = "{\"version\": 2, \"width\": 2, \"height\": 1}\n";
+
+        assert_eq!(read(""), io::ErrorKind::InvalidData);
+        assert_eq!(
+            read("{\"version\": 1, \"width\": 2, \"height\": 1}"),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(
+            read("{\"version\": 2, \"width\": 2}"),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(
+            read(&format!("{}[1, \"o\"]", header)),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(
+            read(&format!("{}[-1, \"o\", \"a\"]", header)),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(
+            read(&format!("{}[1e30, \"o\", \"a\"]", header)),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn recorded_grids_play_back() {
+        let charmap = CharMap::cp437();
+        let mut recorder = CastRecorder::new(TerminalRenderer::new(AnsiColorMode::Ansi16));
+
+        let color = |[r, g, b]: [u8; 3]| TestColor([r, g, b, 255]);
+        let mut grid = BunnyGrid::new((3, 2));
+        grid.set(
+            0,
+            0,
+            Some(BunnyChar::new(
+                b'A' as usize,
+                color(VGA_PALETTE[12]),
+                Some(color(VGA_PALETTE[1])),
+                CharRotation::None,
+                CharMirror::None,
+            )),
+        );
+
+        recorder.push(&grid, &charmap, Duration::from_secs(0));
+        recorder.push(&grid, &charmap, Duration::from_secs(1));
+
+        let mut changed = grid.clone();
+        changed.set(0, 0, None);
+        changed.set(
+            2,
+            1,
+            Some(BunnyChar::new(
+                0xdb,
+                color(VGA_PALETTE[7]),
+                None,
+                CharRotation::None,
+                CharMirror::None,
+            )),
+        );
+
+        recorder.push(&changed, &charmap, Duration::from_secs(2));
+
+        let cast = recorder.into_cast();
+        assert_eq!((cast.width, cast.height), (3, 2));
+        assert_eq!(cast.events.len(), 2);
+
+        let frames = cast.frames::<TestColor>(&charmap);
+        assert_eq!(frames[0].0, Duration::from_secs(0));
+        assert_eq!(cells(&frames[0].1), cells(&grid));
+        assert_eq!(frames[1].0, Duration::from_secs(2));
+        assert_eq!(cells(&frames[1].1), cells(&changed));
+    }
+
+    #[test]
+    fn unknown_chars_become_empty_cells() {
+        let mut cast = Asciicast::new((3, 1));
+        cast.events.push(CastEvent {
+            time: Duration::from_secs(0),
+            output: "\x1b[0;91mx€\x1b[2;1Hy".to_string(),
+        });
+
+        let frames = cast.frames::<TestColor>(&CharMap::cp437());
+        let grid = &frames[0].1;
+
+        assert_eq!(grid.get(0, 0).map(|cell| cell.index), Some(b'x' as usize));
+        assert_eq!(
+            grid.get(0, 0).map(|cell| cell.foreground),
+            Some(TestColor([255, 85, 85, 255]))
+        );
+        assert!(grid.get(1, 0).is_none());
+        assert!(grid.get(2, 0).is_none());
+    }
+}
@@ -0,0 +1,564 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io,
+    ops::Range,
+};
+
+use crate::{
+    char::BunnyChar,
+    char_transforms::{CharMirror, CharRotation},
+    formats::{invalid_data, ByteReader},
+    grid::{BunnyGrid, CellChange, GridDiff},
+    traits::color::Color,
+};
+
+//Deltas are laid out as
+//
+//  "BDLT", u8 version, width, height
+//  the number of colours, then each colour as RGBA bytes
+//  the number of spans, then each span as the cells skipped since the last span, the number of cells and the cell
+//
+//All numbers other than the version are unsigned LEB128 varints
+//A span covers a run of identical cells in row order, and a cell is a flags byte followed by
+//the glyph, the palette index of the foreground and, if the flags say there is one, the palette index of the background
+//Cells that are None are just the flags byte
+
+const DELTA_ID: &[u8; 4] = b"BDLT";
+
+//Bumped whenever the layout changes, so that clients can reject deltas they can't read
+pub const DELTA_VERSION: u8 = 1;
+
+//The most cells a delta's grid can have, so that a small corrupt delta can't expand into a huge number of changes
+const MAX_CELLS: usize = 4096 * 4096;
+
+const PRESENT_FLAG: u8 = 1 << 0;
+const BACKGROUND_FLAG: u8 = 1 << 1;
+const ROTATION_SHIFT: u8 = 2;
+const MIRROR_SHIFT: u8 = 4;
+
+//Encodes a diff for sending to clients, which apply it with apply_delta
+//Changes outside of the diff's dimensions are left out, and when a cell changes more than once the last change wins
+//A client can be brought in sync from nothing with the diff of its grid against an empty grid
+pub fn encode_delta<C>(diff: &GridDiff<C>) -> Vec<u8>
+where
+    C: Color,
+{
+    let (width, height) = diff.dimensions;
+
+    let mut changes: Vec<(usize, &CellChange<C>)> = diff
+        .changes
+        .iter()
+        .filter(|change| change.pos.0 < width && change.pos.1 < height)
+        .map(|change| (change.pos.1 * width + change.pos.0, change))
+        .collect();
+
+    //Sorting is stable, so after removing repeats of a position only its last change is kept
+    changes.sort_by_key(|(index, _)| *index);
+    changes.reverse();
+    changes.dedup_by_key(|(index, _)| *index);
+    changes.reverse();
+
+    let mut palette = Palette::default();
+    let mut spans: Vec<Span> = Vec::new();
+    let mut end = 0;
+
+    for (index, change) in changes {
+        let cell = change.cell.map(|bunny_char| EncodedChar {
+            index: bunny_char.index,
+            foreground: palette.index_of(bunny_char.foreground),
+            background: bunny_char
+                .background
+                .map(|background| palette.index_of(background)),
+            rotation: bunny_char.rotation,
+            mirror: bunny_char.mirror,
+        });
+
+        match spans.last_mut() {
+            Some(span) if index == end && span.cell == cell => span.count += 1,
+            _ => spans.push(Span {
+                skip: index - end,
+                count: 1,
+                cell,
+            }),
+        }
+
+        end = index + 1;
+    }
+
+    let mut bytes = DELTA_ID.to_vec();
+    bytes.push(DELTA_VERSION);
+    write_varint(&mut bytes, width);
+    write_varint(&mut bytes, height);
+
+    write_varint(&mut bytes, palette.colors.len());
+
+    for color in &palette.colors {
+        bytes.extend_from_slice(color);
+    }
+
+    write_varint(&mut bytes, spans.len());
+
+    for span in &spans {
+        write_varint(&mut bytes, span.skip);
+        write_varint(&mut bytes, span.count);
+
+        match &span.cell {
+            Some(cell) => {
+                let mut flags = PRESENT_FLAG
+                    | rotation_bits(cell.rotation) << ROTATION_SHIFT
+                    | mirror_bits(cell.mirror) << MIRROR_SHIFT;
+
+                if cell.background.is_some() {
+                    flags |= BACKGROUND_FLAG;
+                }
+
+                bytes.push(flags);
+                write_varint(&mut bytes, cell.index);
+                write_varint(&mut bytes, cell.foreground);
+
+                if let Some(background) = cell.background {
+                    write_varint(&mut bytes, background);
+                }
+            }
+            None => bytes.push(0),
+        }
+    }
+
+    bytes
+}
+
+pub fn decode_delta<C>(bytes: &[u8]) -> io::Result<GridDiff<C>>
+where
+    C: Color,
+{
+    let DecodedDelta { dimensions, spans } = read_delta(bytes)?;
+    let (width, _) = dimensions;
+
+    let changes = spans
+        .into_iter()
+        .flat_map(|DecodedSpan { indices, cell }| {
+            indices.map(move |index| CellChange {
+                pos: (index % width, index / width),
+                cell,
+            })
+        })
+        .collect();
+
+    Ok(GridDiff {
+        dimensions,
+        changes,
+    })
+}
+
+//Decodes a delta and applies it to a client's grid, the changed cells are marked dirty so the next draw_grid picks them up
+//The grid is left as it was when the delta is corrupt
+pub fn apply_delta<C>(bytes: &[u8], grid: &mut BunnyGrid<C>) -> io::Result<()>
+where
+    C: Color,
+{
+    let DecodedDelta { dimensions, spans } = read_delta(bytes)?;
+    let (width, _) = dimensions;
+
+    if grid.dimensions() != dimensions {
+        grid.resize(dimensions);
+    }
+
+    for span in spans {
+        for index in span.indices {
+            grid.set(index % width, index / width, span.cell);
+        }
+    }
+
+    Ok(())
+}
+
+//Reads a delta's dimensions and spans, checking that every span is inside the grid
+fn read_delta<C>(bytes: &[u8]) -> io::Result<DecodedDelta<C>>
+where
+    C: Color,
+{
+    let mut reader = ByteReader::new(bytes);
+
+    if reader.take(DELTA_ID.len())? != DELTA_ID {
+        return Err(invalid_data("not a grid delta"));
+    }
+
+    if reader.read_u8()? != DELTA_VERSION {
+        return Err(invalid_data("unsupported grid delta version"));
+    }
+
+    let width = reader.read_varint()?;
+    let height = reader.read_varint()?;
+    let cell_count = width
+        .checked_mul(height)
+        .filter(|cell_count| *cell_count <= MAX_CELLS)
+        .ok_or_else(|| invalid_data("grid delta dimensions are too large"))?;
+
+    //Every colour takes at least 4 bytes, which keeps a corrupt count from allocating a huge palette
+    let color_count = reader.read_varint()?;
+
+    if color_count > reader.remaining() / 4 {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+    }
+
+    let mut palette = Vec::with_capacity(color_count);
+
+    for _ in 0..color_count {
+        let rgba = reader.take(4)?;
+        palette.push(C::from_rgba8([rgba[0], rgba[1], rgba[2], rgba[3]]));
+    }
+
+    let color = |index: usize| {
+        palette
+            .get(index)
+            .copied()
+            .ok_or_else(|| invalid_data("grid delta colour is not in its palette"))
+    };
+
+    //Likewise every span takes at least 3 bytes
+    let span_count = reader.read_varint()?;
+
+    if span_count > reader.remaining() / 3 {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+    }
+
+    let mut spans = Vec::with_capacity(span_count);
+    let mut end: usize = 0;
+
+    for _ in 0..span_count {
+        let start = end
+            .checked_add(reader.read_varint()?)
+            .ok_or_else(|| invalid_data("grid delta span is outside the grid"))?;
+        let count = reader.read_varint()?;
+
+        end = start
+            .checked_add(count)
+            .filter(|end| *end <= cell_count)
+            .ok_or_else(|| invalid_data("grid delta span is outside the grid"))?;
+
+        let flags = reader.read_u8()?;
+
+        let cell = if flags & PRESENT_FLAG != 0 {
+            let index = reader.read_varint()?;
+            let foreground = color(reader.read_varint()?)?;
+
+            let background = if flags & BACKGROUND_FLAG != 0 {
+                Some(color(reader.read_varint()?)?)
+            } else {
+                None
+            };
+
+            Some(BunnyChar::new(
+                index,
+                foreground,
+                background,
+                rotation_from_bits(flags >> ROTATION_SHIFT),
+                mirror_from_bits(flags >> MIRROR_SHIFT),
+            ))
+        } else {
+            None
+        };
+
+        spans.push(DecodedSpan {
+            indices: start..end,
+            cell,
+        });
+    }
+
+    Ok(DecodedDelta {
+        dimensions: (width, height),
+        spans,
+    })
+}
+
+//A cell with its colours replaced by palette indices
+#[derive(Clone, Copy, PartialEq)]
+struct EncodedChar {
+    index: usize,
+    foreground: usize,
+    background: Option<usize>,
+    rotation: CharRotation,
+    mirror: CharMirror,
+}
+
+struct Span {
+    skip: usize,
+    count: usize,
+    cell: Option<EncodedChar>,
+}
+
+struct DecodedDelta<C> {
+    dimensions: (usize, usize),
+    spans: Vec<DecodedSpan<C>>,
+}
+
+//A run of cells in row order all set to the same cell
+struct DecodedSpan<C> {
+    indices: Range<usize>,
+    cell: Option<BunnyChar<C>>,
+}
+
+//The colours of a delta in the order they are first used
+#[derive(Default)]
+struct Palette {
+    colors: Vec<[u8; 4]>,
+    indices: HashMap<[u8; 4], usize>,
+}
+
+impl Palette {
+    fn index_of<C>(&mut self, color: C) -> usize
+    where
+        C: Color,
+    {
+        let rgba = color.to_rgba8();
+
+        match self.indices.entry(rgba) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                self.colors.push(rgba);
+                *entry.insert(self.colors.len() - 1)
+            }
+        }
+    }
+}
+
+fn rotation_bits(rotation: CharRotation) -> u8 {
+    match rotation {
+        CharRotation::None => 0,
+        CharRotation::Rotation90 => 1,
+        CharRotation::Rotation180 => 2,
+        CharRotation::Rotation270 => 3,
+    }
+}
+
+fn rotation_from_bits(bits: u8) -> CharRotation {
+    match bits & 0b11 {
+        0 => CharRotation::None,
+        1 => CharRotation::Rotation90,
+        2 => CharRotation::Rotation180,
+        _ => CharRotation::Rotation270,
+    }
+}
+
+fn mirror_bits(mirror: CharMirror) -> u8 {
+    match mirror {
+        CharMirror::None => 0,
+        CharMirror::MirrorX => 1,
+        CharMirror::MirrorY => 2,
+        CharMirror::MirrorBoth => 3,
+    }
+}
+
+fn mirror_from_bits(bits: u8) -> CharMirror {
+    match bits & 0b11 {
+        0 => CharMirror::None,
+        1 => CharMirror::MirrorX,
+        2 => CharMirror::MirrorY,
+        _ => CharMirror::MirrorBoth,
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push(value as u8 & 0x7f | 0x80);
+        value >>= 7;
+    }
+
+    bytes.push(value as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::color::TestColor;
+
+    const WHITE: TestColor = TestColor([255; 4]);
+    const BLUE: TestColor = TestColor([0, 0, 255, 255]);
+
+    fn cell(index: usize) -> Option<BunnyChar<TestColor>> {
+        Some(BunnyChar::new(
+            index,
+            WHITE,
+            None,
+            CharRotation::None,
+            CharMirror::None,
+        ))
+    }
+
+    fn cells(grid: &BunnyGrid<TestColor>) -> Vec<Option<BunnyChar<TestColor>>> {
+        grid.cells().map(|(_, cell)| cell.copied()).collect()
+    }
+
+    fn grid() -> BunnyGrid<TestColor> {
+        let mut grid = BunnyGrid::new((4, 3));
+
+        for x in 0..4 {
+            grid.set(x, 0, cell(b'#' as usize));
+        }
+
+        grid.set(
+            1,
+            1,
+            Some(BunnyChar::new(
+                300,
+                BLUE,
+                Some(WHITE),
+                CharRotation::Rotation270,
+                CharMirror::MirrorY,
+            )),
+        );
+        grid.set(3, 2, cell(b'@' as usize));
+        grid
+    }
+
+    #[test]
+    fn deltas_have_a_fixed_layout() {
+        let diff = GridDiff {
+            dimensions: (3, 1),
+            changes: vec![
+                CellChange {
+                    pos: (1, 0),
+                    cell: cell(b'A' as usize),
+                },
+                CellChange {
+                    pos: (2, 0),
+                    cell: cell(b'A' as usize),
+                },
+            ],
+        };
+
+        assert_eq!(
+            encode_delta(&diff),
+            b"BDLT\x01\x03\x01\x01\xff\xff\xff\xff\x01\x01\x02\x01\x41\x00"
+        );
+    }
+
+    #[test]
+    fn deltas_round_trip() {
+        let diff = BunnyGrid::diff(&BunnyGrid::new((0, 0)), &grid());
+
+        assert_eq!(
+            decode_delta::<TestColor>(&encode_delta(&diff)).unwrap(),
+            diff
+        );
+
+        let mut changed = grid();
+        changed.set(0, 0, None);
+        changed.set(2, 2, cell(b'@' as usize));
+
+        let diff = BunnyGrid::diff(&grid(), &changed);
+
+        assert_eq!(diff.changes.len(), 2);
+        assert_eq!(
+            decode_delta::<TestColor>(&encode_delta(&diff)).unwrap(),
+            diff
+        );
+    }
+
+    #[test]
+    fn applied_deltas_bring_clients_in_sync() {
+        let server = grid();
+        let mut client = BunnyGrid::new((1, 1));
+
+        apply_delta(
+            &encode_delta(&BunnyGrid::diff(&BunnyGrid::new((0, 0)), &server)),
+            &mut client,
+        )
+        .unwrap();
+
+        assert_eq!(client.dimensions(), server.dimensions());
+        assert_eq!(cells(&client), cells(&server));
+
+        let mut changed = server.clone();
+        changed.set(1, 1, None);
+        client.mark_clean();
+
+        apply_delta(
+            &encode_delta(&BunnyGrid::diff(&server, &changed)),
+            &mut client,
+        )
+        .unwrap();
+
+        assert_eq!(cells(&client), cells(&changed));
+        assert_eq!(
+            client.dirty_cells().map(|(pos, _)| pos).collect::<Vec<_>>(),
+            vec![(1, 1)]
+        );
+    }
+
+    #[test]
+    fn the_last_change_to_a_cell_wins() {
+        let change = |pos, index| CellChange {
+            pos,
+            cell: cell(index),
+        };
+
+        let diff = GridDiff {
+            dimensions: (2, 2),
+            changes: vec![
+                change((1, 1), 1),
+                change((0, 0), 2),
+                change((5, 0), 3),
+                change((1, 1), 4),
+            ],
+        };
+
+        assert_eq!(
+            decode_delta::<TestColor>(&encode_delta(&diff))
+                .unwrap()
+                .changes,
+            vec![change((0, 0), 2), change((1, 1), 4)]
+        );
+    }
+
+    #[test]
+    fn corrupt_deltas() {
+        let decode = |bytes: &[u8]| decode_delta::<TestColor>(bytes).unwrap_err().kind();
+        let delta = encode_delta(&BunnyGrid::diff(&BunnyGrid::new((0, 0)), &grid()));
+
+        assert_eq!(decode(b"BDLX\x01"), io::ErrorKind::InvalidData);
+        assert_eq!(decode(b"BDLT\x02"), io::ErrorKind::InvalidData);
+        assert_eq!(
+            decode(&delta[..delta.len() - 1]),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        //A 1 by 1 grid with a span that starts past its only cell
+        assert_eq!(
+            decode(b"BDLT\x01\x01\x01\x00\x01\x01\x01\x00"),
+            io::ErrorKind::InvalidData
+        );
+
+        //A colour index past the end of the palette
+        assert_eq!(
+            decode(b"BDLT\x01\x01\x01\x00\x01\x00\x01\x01\x41\x00"),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn tiny_deltas_cannot_expand_into_huge_diffs() {
+        //A 2^28 by 2^28 grid
+        assert_eq!(
+            decode_delta::<TestColor>(b"BDLT\x01\x80\x80\x80\x80\x01\x80\x80\x80\x80\x01\x00\x00")
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        //Far more spans than the bytes left could hold
+        assert_eq!(
+            decode_delta::<TestColor>(b"BDLT\x01\x01\x01\x00\xff\xff\xff\xff\x0f\x00\x01\x00")
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        let mut grid = grid();
+        let before = cells(&grid);
+
+        assert!(apply_delta(b"BDLT\x01\x01\x01\x00\x01\x00\x02\x00", &mut grid).is_err());
+        assert_eq!(grid.dimensions(), (4, 3));
+        assert_eq!(cells(&grid), before);
+    }
+}
@@ -0,0 +1,286 @@
+use crate::{
+    char::BunnyChar,
+    char_transforms::{orientation_matrix, CharMirror, CharRotation},
+    charmap::CharMap,
+    font::BunnyFont,
+    grid::BunnyGrid,
+    traits::{color::Color, source_image::SourceImage},
+};
+
+//A colour as CSS, using rgba() only when it isn't opaque
+pub(crate) fn css_color<C>(color: C) -> String
+where
+    C: Color,
+{
+    match color.to_rgba8() {
+        [r, g, b, 255] => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        [r, g, b, a] => format!("rgba({},{},{},{:.3})", r, g, b, a as f32 / 255.0),
+    }
+}
+
+//The linear part of a char's orientation as the a, b, c and d of a CSS or SVG matrix, for transforms around the char's centre
+pub(crate) fn orientation_css_matrix<C>(bunny_char: &BunnyChar<C>) -> Option<[i32; 4]> {
+    if (bunny_char.rotation, bunny_char.mirror) == (CharRotation::None, CharMirror::None) {
+        return None;
+    }
+
+    let [[a, c], [b, d]] = orientation_matrix(bunny_char.rotation, bunny_char.mirror);
+    Some([a, b, c, d])
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.chars()
+        .map(|ch| match ch {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            ch => ch.to_string(),
+        })
+        .collect()
+}
+
+fn page(title: &str, style: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_xml(title),
+        style,
+        body
+    )
+}
+
+//Exports a grid as text in a page, with each run of cells that share colours in a span
+//Glyphs are turned into chars through the charmap, rotated and mirrored glyphs are transformed with CSS
+pub fn export_html<C>(grid: &BunnyGrid<C>, charmap: &CharMap, title: &str) -> String
+where
+    C: Color,
+{
+    let (width, height) = grid.dimensions();
+    let mut body = String::from("<pre>");
+
+    for y in 0..height {
+        let mut run_style: Option<String> = None;
+
+        for x in 0..width {
+            let cell = grid.get(x, y);
+
+            let style = cell.map(|bunny_char| {
+                let mut style = format!("color:{}", css_color(bunny_char.foreground));
+
+                if let Some(background) = bunny_char.background {
+                    style += &format!(";background:{}", css_color(background));
+                }
+
+                style
+            });
+
+            if style != run_style {
+                if run_style.is_some() {
+                    body += "</span>";
+                }
+
+                if let Some(style) = &style {
+                    body += &format!("<span style=\"{}\">", style);
+                }
+
+                run_style = style;
+            }
+
+            let ch = cell
+                .and_then(|bunny_char| charmap.char_of(bunny_char.index))
+                .unwrap_or(' ');
+            let text = escape_xml(&ch.to_string());
+
+            match cell.and_then(orientation_css_matrix) {
+                Some([a, b, c, d]) => {
+                    body += &format!(
+                        "<span class=\"oriented\" style=\"transform:matrix({},{},{},{},0,0)\">{}</span>",
+                        a, b, c, d, text
+                    )
+                }
+                None => body += &text,
+            }
+        }
+
+        if run_style.is_some() {
+            body += "</span>";
+        }
+
+        body += "\n";
+    }
+
+    body += "</pre>\n";
+
+    page(
+        title,
+        "body { background: #000; color: #fff; }\npre { font-family: monospace; line-height: 1; }\n.oriented { display: inline-block; }\n",
+        &body,
+    )
+}
+
+//Exports a grid as a page drawing every glyph from the font's atlas at the given url, so that it looks exactly like it does in game
+//Glyphs are cut out of the atlas as a CSS mask and filled with their foreground colour, and rotated and mirrored with CSS
+pub fn export_html_sprites<T, C>(
+    grid: &BunnyGrid<C>,
+    font: &BunnyFont<T>,
+    atlas_url: &str,
+    scale: usize,
+    title: &str,
+) -> String
+where
+    T: SourceImage,
+    C: Color,
+{
+    let (width, height) = grid.dimensions();
+    let (char_width, char_height) = font.char_dimensions();
+    let (texture_width, texture_height) = font.texture().get_pixel_dimensions();
+    let (tile_width, tile_height) = (char_width * scale, char_height * scale);
+
+    let style = format!(
+        "body {{ background: #000; }}\n\
+         .grid {{ position: relative; width: {}px; height: {}px; }}\n\
+         .cell {{ position: absolute; width: {}px; height: {}px; }}\n\
+         .glyph {{ width: 100%; height: 100%; \
+         -webkit-mask-image: url(\"{url}\"); mask-image: url(\"{url}\"); \
+         -webkit-mask-size: {mask_width}px {mask_height}px; mask-size: {mask_width}px {mask_height}px; \
+         mask-mode: luminance; image-rendering: pixelated; }}\n",
+        width * tile_width,
+        height * tile_height,
+        tile_width,
+        tile_height,
+        url = escape_xml(atlas_url),
+        mask_width = texture_width * scale,
+        mask_height = texture_height * scale,
+    );
+
+    let mut body = String::from("<div class=\"grid\">\n");
+
+    for ((x, y), cell) in grid.cells() {
+        let bunny_char = match cell {
+            Some(bunny_char) => bunny_char,
+            None => continue,
+        };
+
+        let (src_x, src_y, _, _) = font.get_src_uvs(bunny_char.index);
+        let (mask_x, mask_y) = (
+            (src_x * texture_width as f32).round() as usize * scale,
+            (src_y * texture_height as f32).round() as usize * scale,
+        );
+
+        let mut cell_style = format!("left:{}px;top:{}px", x * tile_width, y * tile_height);
+
+        if let Some(background) = bunny_char.background {
+            cell_style += &format!(";background:{}", css_color(background));
+        }
+
+        let mut glyph_style = format!(
+            "background:{};-webkit-mask-position:-{}px -{}px;mask-position:-{}px -{}px",
+            css_color(bunny_char.foreground),
+            mask_x,
+            mask_y,
+            mask_x,
+            mask_y
+        );
+
+        if let Some([a, b, c, d]) = orientation_css_matrix(bunny_char) {
+            glyph_style += &format!(";transform:matrix({},{},{},{},0,0)", a, b, c, d);
+        }
+
+        body += &format!(
+            "<div class=\"cell\" style=\"{}\"><div class=\"glyph\" style=\"{}\"></div></div>\n",
+            cell_style, glyph_style
+        );
+    }
+
+    body += "</div>\n";
+
+    page(title, &style, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::{color::TestColor, source_image::TestImage};
+
+    const RED: TestColor = TestColor([255, 0, 0, 255]);
+
+    fn cell(ch: char) -> Option<BunnyChar<TestColor>> {
+        Some(BunnyChar::new(
+            CharMap::cp437().index_of(ch).unwrap(),
+            RED,
+            None,
+            CharRotation::None,
+            CharMirror::None,
+        ))
+    }
+
+    fn grid(cells: &[Option<BunnyChar<TestColor>>]) -> BunnyGrid<TestColor> {
+        let mut grid = BunnyGrid::new((cells.len(), 1));
+
+        for (x, cell) in cells.iter().enumerate() {
+            grid.set(x, 0, *cell);
+        }
+
+        grid
+    }
+
+    #[test]
+    fn css_colours() {
+        assert_eq!(css_color(RED), "#ff0000");
+        assert_eq!(css_color(TestColor([1, 2, 3, 51])), "rgba(1,2,3,0.200)");
+    }
+
+    #[test]
+    fn orientations_are_css_matrices() {
+        let oriented = |rotation, mirror| {
+            orientation_css_matrix(&cell('a').unwrap().oriented(rotation, mirror))
+        };
+
+        assert_eq!(oriented(CharRotation::None, CharMirror::None), None);
+        assert_eq!(
+            oriented(CharRotation::Rotation90, CharMirror::None),
+            Some([0, 1, -1, 0])
+        );
+        assert_eq!(
+            oriented(CharRotation::None, CharMirror::MirrorX),
+            Some([-1, 0, 0, 1])
+        );
+    }
+
+    #[test]
+    fn runs_of_cells_share_a_span() {
+        let html = export_html(
+            &grid(&[
+                cell('a'),
+                cell('<'),
+                None,
+                cell('>').map(|cell| cell.rotation(CharRotation::Rotation90)),
+            ]),
+            &CharMap::cp437(),
+            "a & b",
+        );
+
+        assert!(html.contains("<title>a &amp; b</title>"));
+        assert!(html.contains(
+            "<pre><span style=\"color:#ff0000\">a&lt;</span> <span style=\"color:#ff0000\">\
+             <span class=\"oriented\" style=\"transform:matrix(0,1,-1,0,0,0)\">&gt;</span></span>\n</pre>"
+        ));
+    }
+
+    #[test]
+    fn sprites_are_masked_out_of_the_atlas() {
+        let font = BunnyFont::new(TestImage((128, 64)), (8, 8));
+        let mut grid = grid(&[None, cell('A')]);
+        grid.set(0, 0, cell('a').map(|cell| cell.background(Some(RED))));
+
+        let html = export_html_sprites(&grid, &font, "font.png", 2, "");
+
+        assert!(html.contains("mask-size: 256px 128px"));
+        assert!(html.contains(".grid { position: relative; width: 32px; height: 16px; }"));
+        assert!(html.contains(
+            "<div class=\"cell\" style=\"left:16px;top:0px\"><div class=\"glyph\" \
+             style=\"background:#ff0000;-webkit-mask-position:-16px -64px;mask-position:-16px -64px\"></div></div>"
+        ));
+        assert!(html.contains("style=\"left:0px;top:0px;background:#ff0000\""));
+    }
+}
@@ -0,0 +1,77 @@
+use image::RgbaImage;
+
+use crate::integrations::image::base64;
+
+//The most base64 the kitty graphics protocol allows in a single escape sequence
+const CHUNK_LENGTH: usize = 4096;
+
+//Encodes an image as kitty graphics protocol escape sequences that display it at the cursor
+//The pixels are sent as raw RGBA, split over as many escape sequences as needed, and the terminal is asked not to reply
+pub fn encode_kitty(image: &RgbaImage) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+    let data = base64(image.as_raw());
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(CHUNK_LENGTH).collect();
+
+    let mut bytes = Vec::new();
+
+    //An image without pixels is still sent as a single empty chunk
+    if chunks.is_empty() {
+        bytes.extend(format!("\x1b_Ga=T,f=32,s={},v={},q=2;\x1b\\", width, height).bytes());
+        return bytes;
+    }
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = if index + 1 < chunks.len() { 1 } else { 0 };
+
+        //Only the first chunk carries the image's details, the rest only say whether more follow
+        if index == 0 {
+            bytes
+                .extend(format!("\x1b_Ga=T,f=32,s={},v={},q=2,m={};", width, height, more).bytes());
+        } else {
+            bytes.extend(format!("\x1b_Gm={};", more).bytes());
+        }
+
+        bytes.extend_from_slice(chunk);
+        bytes.extend(b"\x1b\\");
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn small_images_are_a_single_chunk() {
+        let mut image = RgbaImage::new(2, 1);
+        image.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        image.put_pixel(1, 0, Rgba([0, 0, 255, 255]));
+
+        assert_eq!(
+            encode_kitty(&image),
+            b"\x1b_Ga=T,f=32,s=2,v=1,q=2,m=0;/wAA/wAA//8=\x1b\\"
+        );
+        assert_eq!(
+            encode_kitty(&RgbaImage::new(0, 0)),
+            b"\x1b_Ga=T,f=32,s=0,v=0,q=2;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn large_images_are_split_into_chunks() {
+        //769 pixels take 4104 chars of base64, a full chunk and 8 chars
+        let image = RgbaImage::from_fn(769, 1, |x, _| Rgba([x as u8, 1, 2, 255]));
+        let data = base64(image.as_raw());
+
+        let mut expected = b"\x1b_Ga=T,f=32,s=769,v=1,q=2,m=1;".to_vec();
+        expected.extend(data[..4096].bytes());
+        expected.extend(b"\x1b\\\x1b_Gm=0;");
+        expected.extend(data[4096..].bytes());
+        expected.extend(b"\x1b\\");
+
+        assert_eq!(data.len(), 4104);
+        assert_eq!(encode_kitty(&image), expected);
+    }
+}
@@ -0,0 +1,378 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+use crate::{
+    char::BunnyChar,
+    char_transforms::{CharMirror, CharRotation},
+    charmap::{cp437_code, oriented_char, CP437_CHARS},
+    formats::invalid_data,
+    grid::BunnyGrid,
+    traits::color::Color,
+};
+
+//REXPaint .xp files are gzipped, little endian and laid out as
+//
+//  i32 version, i32 layer count
+//  for each layer: i32 width, i32 height, then for each cell in column major order
+//      u32 glyph index, u8 foreground r g b, u8 background r g b
+//
+//Glyph indices are CP437 codes, which line up with the indices of a CP437 font
+//A background of pure magenta marks a cell as transparent
+
+const XP_VERSION: i32 = -1;
+const TRANSPARENT_BACKGROUND: [u8; 3] = [255, 0, 255];
+
+//The cells REXPaint leaves in transparent areas, which are read back as empty cells
+const BLANK_GLYPHS: [u32; 2] = [0, 32];
+
+//Far larger than anything REXPaint makes, but small enough that a corrupt size can't allocate a huge grid
+const MAX_LAYER_CELLS: usize = 4096 * 4096;
+
+//A REXPaint image, each layer is drawn on top of the ones before it
+#[derive(Clone, Debug)]
+pub struct XpImage<C> {
+    pub layers: Vec<BunnyGrid<C>>,
+}
+
+impl<C> XpImage<C>
+where
+    C: Color,
+{
+    pub fn new(layers: Vec<BunnyGrid<C>>) -> Self {
+        Self { layers }
+    }
+
+    //The size of the largest layer, REXPaint layers are normally all the same size
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.layers.iter().map(|layer| layer.dimensions()).fold(
+            (0, 0),
+            |(width, height), (layer_width, layer_height)| {
+                (width.max(layer_width), height.max(layer_height))
+            },
+        )
+    }
+
+    //Merges the layers into a single grid, empty cells let the layers below show through
+    pub fn flatten(&self) -> BunnyGrid<C> {
+        let mut flattened = BunnyGrid::new(self.dimensions());
+
+        for layer in &self.layers {
+            flattened.paste_region(layer, (0, 0), true);
+        }
+
+        flattened
+    }
+
+    pub fn load<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn save<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    //Reads a gzipped .xp file
+    pub fn read<R>(reader: R) -> io::Result<Self>
+    where
+        R: Read,
+    {
+        let mut reader = GzDecoder::new(reader);
+
+        let version = read_i32(&mut reader)?;
+
+        if version != XP_VERSION {
+            return Err(invalid_data(format!("unsupported xp version {}", version)));
+        }
+
+        let layer_count = read_i32(&mut reader)?;
+
+        if layer_count < 0 {
+            return Err(invalid_data(format!("invalid layer count {}", layer_count)));
+        }
+
+        let layers = (0..layer_count)
+            .map(|_| read_layer(&mut reader))
+            .collect::<io::Result<_>>()?;
+
+        Ok(Self { layers })
+    }
+
+    //Writes a gzipped .xp file
+    //REXPaint has no rotation or mirroring, so rotated and mirrored cells are written as the code page 437 glyph
+    //that looks like them, or upright when there is none
+    pub fn write<W>(&self, writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut writer = GzEncoder::new(writer, Compression::default());
+
+        writer.write_all(&XP_VERSION.to_le_bytes())?;
+        writer.write_all(&(self.layers.len() as i32).to_le_bytes())?;
+
+        for layer in &self.layers {
+            write_layer(&mut writer, layer)?;
+        }
+
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+fn from_rgb<C>([r, g, b]: [u8; 3]) -> C
+where
+    C: Color,
+{
+    C::from_rgba8([r, g, b, 255])
+}
+
+fn to_rgb<C>(color: C) -> [u8; 3]
+where
+    C: Color,
+{
+    let [r, g, b, _] = color.to_rgba8();
+    [r, g, b]
+}
+
+fn read_i32<R>(reader: &mut R) -> io::Result<i32>
+where
+    R: Read,
+{
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(i32::from_le_bytes(bytes))
+}
+
+fn read_layer<R, C>(reader: &mut R) -> io::Result<BunnyGrid<C>>
+where
+    R: Read,
+    C: Color,
+{
+    let width = read_i32(reader)?;
+    let height = read_i32(reader)?;
+
+    let cell_count = (width as usize)
+        .checked_mul(height as usize)
+        .filter(|cell_count| *cell_count <= MAX_LAYER_CELLS);
+
+    if width < 0 || height < 0 || cell_count.is_none() {
+        return Err(invalid_data(format!(
+            "invalid layer size {}x{}",
+            width, height
+        )));
+    }
+
+    let mut layer = BunnyGrid::new((width as usize, height as usize));
+
+    for x in 0..width as usize {
+        for y in 0..height as usize {
+            let mut cell = [0; 10];
+            reader.read_exact(&mut cell)?;
+
+            let glyph = u32::from_le_bytes([cell[0], cell[1], cell[2], cell[3]]);
+            let foreground = [cell[4], cell[5], cell[6]];
+            let background = [cell[7], cell[8], cell[9]];
+
+            let transparent = background == TRANSPARENT_BACKGROUND;
+
+            if transparent && BLANK_GLYPHS.contains(&glyph) {
+                continue;
+            }
+
+            layer.set(
+                x,
+                y,
+                Some(BunnyChar::new(
+                    glyph as usize,
+                    from_rgb(foreground),
+                    if transparent {
+                        None
+                    } else {
+                        Some(from_rgb(background))
+                    },
+                    CharRotation::None,
+                    CharMirror::None,
+                )),
+            );
+        }
+    }
+
+    Ok(layer)
+}
+
+//The code page 437 glyph that looks like a char drawn with its orientation, or its own glyph when there is none
+fn upright_glyph<C>(bunny_char: &BunnyChar<C>) -> usize {
+    if (bunny_char.rotation, bunny_char.mirror) == (CharRotation::None, CharMirror::None) {
+        return bunny_char.index;
+    }
+
+    CP437_CHARS
+        .get(bunny_char.index)
+        .and_then(|ch| oriented_char(*ch, bunny_char.rotation, bunny_char.mirror))
+        .and_then(cp437_code)
+        .map_or(bunny_char.index, |code| code as usize)
+}
+
+fn write_layer<W, C>(writer: &mut W, layer: &BunnyGrid<C>) -> io::Result<()>
+where
+    W: Write,
+    C: Color,
+{
+    let (width, height) = layer.dimensions();
+
+    writer.write_all(&(width as i32).to_le_bytes())?;
+    writer.write_all(&(height as i32).to_le_bytes())?;
+
+    for x in 0..width {
+        for y in 0..height {
+            let (glyph, foreground, background) = match layer.get(x, y) {
+                Some(bunny_char) => (
+                    upright_glyph(bunny_char) as u32,
+                    to_rgb(bunny_char.foreground),
+                    bunny_char.background.map_or(TRANSPARENT_BACKGROUND, to_rgb),
+                ),
+                None => (0, [0, 0, 0], TRANSPARENT_BACKGROUND),
+            };
+
+            writer.write_all(&glyph.to_le_bytes())?;
+            writer.write_all(&foreground)?;
+            writer.write_all(&background)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::color::TestColor;
+
+    const RED: TestColor = TestColor([255, 0, 0, 255]);
+    const GREY: TestColor = TestColor([85, 85, 85, 255]);
+
+    fn cell(index: usize, background: Option<TestColor>) -> Option<BunnyChar<TestColor>> {
+        Some(BunnyChar::new(
+            index,
+            RED,
+            background,
+            CharRotation::None,
+            CharMirror::None,
+        ))
+    }
+
+    fn gzipped(ints: &[i32]) -> Vec<u8> {
+        let mut writer = GzEncoder::new(Vec::new(), Compression::default());
+
+        for int in ints {
+            writer.write_all(&int.to_le_bytes()).unwrap();
+        }
+
+        writer.finish().unwrap()
+    }
+
+    fn cells(grid: &BunnyGrid<TestColor>) -> Vec<Option<BunnyChar<TestColor>>> {
+        grid.cells().map(|(_, cell)| cell.copied()).collect()
+    }
+
+    #[test]
+    fn layers_round_trip() {
+        let mut bottom = BunnyGrid::new((3, 2));
+        bottom.fill(cell(0xdb, Some(GREY)));
+
+        let mut top = BunnyGrid::new((3, 2));
+        top.set(1, 0, cell(0x40, None));
+        top.set(2, 1, cell(0x300, Some(RED)));
+
+        let image = XpImage::new(vec![bottom, top]);
+        let mut bytes = Vec::new();
+        image.write(&mut bytes).unwrap();
+
+        let read = XpImage::<TestColor>::read(bytes.as_slice()).unwrap();
+
+        assert_eq!(read.layers.len(), 2);
+        assert_eq!(read.dimensions(), (3, 2));
+
+        for (read, written) in read.layers.iter().zip(&image.layers) {
+            assert_eq!(cells(read), cells(written));
+        }
+
+        let flattened = read.flatten();
+        assert_eq!(flattened.get(0, 0), cell(0xdb, Some(GREY)).as_ref());
+        assert_eq!(flattened.get(1, 0), cell(0x40, None).as_ref());
+    }
+
+    #[test]
+    fn oriented_glyphs_are_written_as_their_substitutes() {
+        let mut layer = BunnyGrid::new((3, 1));
+        layer.set(
+            0,
+            0,
+            cell(b'>' as usize, None).map(|cell| cell.rotation(CharRotation::Rotation90)),
+        );
+        layer.set(
+            1,
+            0,
+            cell(0xda, Some(GREY)).map(|cell| cell.mirror(CharMirror::MirrorX)),
+        );
+        layer.set(
+            2,
+            0,
+            cell(b'(' as usize, None).map(|cell| cell.mirror(CharMirror::MirrorX)),
+        );
+
+        let mut bytes = Vec::new();
+        XpImage::new(vec![layer]).write(&mut bytes).unwrap();
+
+        let read = XpImage::<TestColor>::read(bytes.as_slice()).unwrap();
+        assert_eq!(read.layers[0].get(0, 0), cell(b'v' as usize, None).as_ref());
+        assert_eq!(read.layers[0].get(1, 0), cell(0xbf, Some(GREY)).as_ref());
+        assert_eq!(read.layers[0].get(2, 0), cell(b')' as usize, None).as_ref());
+    }
+
+    #[test]
+    fn orientations_without_substitutes_are_dropped() {
+        let mut layer = BunnyGrid::new((1, 1));
+        layer.set(
+            0,
+            0,
+            cell(1, None).map(|cell| cell.oriented(CharRotation::Rotation90, CharMirror::MirrorX)),
+        );
+
+        let mut bytes = Vec::new();
+        XpImage::new(vec![layer]).write(&mut bytes).unwrap();
+
+        let read = XpImage::<TestColor>::read(bytes.as_slice()).unwrap();
+        assert_eq!(read.layers[0].get(0, 0), cell(1, None).as_ref());
+    }
+
+    #[test]
+    fn corrupt_headers_are_invalid_data() {
+        let read = |ints: &[i32]| {
+            XpImage::<TestColor>::read(gzipped(ints).as_slice())
+                .unwrap_err()
+                .kind()
+        };
+
+        assert_eq!(read(&[1, 1]), io::ErrorKind::InvalidData);
+        assert_eq!(read(&[XP_VERSION, -1]), io::ErrorKind::InvalidData);
+        assert_eq!(read(&[XP_VERSION, 1, -1, 1]), io::ErrorKind::InvalidData);
+        assert_eq!(
+            read(&[XP_VERSION, 1, i32::MAX, i32::MAX]),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(read(&[XP_VERSION, 1, 2, 2]), io::ErrorKind::UnexpectedEof);
+    }
+}
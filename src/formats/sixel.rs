@@ -0,0 +1,213 @@
+use std::collections::{hash_map::Entry, BTreeMap, HashMap};
+
+use image::RgbaImage;
+
+//Sixel images are drawn in bands of six rows, each column of a band written as a char for which colours are set in it
+//Every colour used in a band is drawn in its own pass over the band, returning to its start with $ between passes
+
+//The most colours a sixel image is given, which most terminals support
+const MAX_COLORS: usize = 256;
+
+//Encodes an image as a sixel escape sequence that displays it at the cursor
+//Images with more colours than fit a palette are reduced to a 6x6x6 colour cube, mostly transparent pixels are left undrawn
+pub fn encode_sixel(image: &RgbaImage) -> Vec<u8> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let palette = Palette::new(image);
+
+    //Pixels left undrawn keep whatever the terminal has behind them
+    let mut bytes = b"\x1bP0;1;0q".to_vec();
+    bytes.extend(format!("\"1;1;{};{}", width, height).bytes());
+
+    for (index, [r, g, b]) in palette.colors.iter().enumerate() {
+        //Colours are given as percentages
+        let percent = |channel: u8| (channel as u32 * 100 + 127) / 255;
+        bytes.extend(
+            format!(
+                "#{};2;{};{};{}",
+                index,
+                percent(*r),
+                percent(*g),
+                percent(*b)
+            )
+            .bytes(),
+        );
+    }
+
+    for band_y in (0..height).step_by(6) {
+        //The sixels of each colour used in the band, ordered by colour so that output is always the same
+        let mut passes: BTreeMap<usize, Vec<u8>> = BTreeMap::new();
+
+        for y in band_y..(band_y + 6).min(height) {
+            for x in 0..width {
+                let pixel = image.get_pixel(x as u32, y as u32);
+
+                if pixel.0[3] < 128 {
+                    continue;
+                }
+
+                let color = palette.index_of([pixel.0[0], pixel.0[1], pixel.0[2]]);
+                passes.entry(color).or_insert_with(|| vec![0; width])[x] |= 1 << (y - band_y);
+            }
+        }
+
+        for (pass, (color, sixels)) in passes.iter().enumerate() {
+            if pass > 0 {
+                bytes.push(b'$');
+            }
+
+            bytes.extend(format!("#{}", color).bytes());
+            write_sixels(&mut bytes, sixels);
+        }
+
+        bytes.push(b'-');
+    }
+
+    bytes.extend(b"\x1b\\");
+    bytes
+}
+
+//Writes a pass over a band, with repeated sixels run length encoded and trailing empty ones left out
+fn write_sixels(bytes: &mut Vec<u8>, sixels: &[u8]) {
+    let length = sixels
+        .iter()
+        .rposition(|sixel| *sixel != 0)
+        .map_or(0, |last| last + 1);
+
+    let mut sixels = sixels[..length].iter().peekable();
+
+    while let Some(&sixel) = sixels.next() {
+        let mut count = 1;
+
+        while sixels.next_if_eq(&&sixel).is_some() {
+            count += 1;
+        }
+
+        let ch = b'?' + sixel;
+
+        //A repeat is only shorter than writing the sixel out from four on
+        if count > 3 {
+            bytes.extend(format!("!{}", count).bytes());
+            bytes.push(ch);
+        } else {
+            bytes.extend(std::iter::repeat_n(ch, count));
+        }
+    }
+}
+
+struct Palette {
+    colors: Vec<[u8; 3]>,
+    //The index of every colour in the image when they all fit, otherwise colours are looked up in the colour cube
+    indices: Option<HashMap<[u8; 3], usize>>,
+}
+
+impl Palette {
+    //The colours of the drawn pixels in the order they first appear, or a colour cube if there are too many
+    fn new(image: &RgbaImage) -> Self {
+        let mut colors = Vec::new();
+        let mut indices = HashMap::new();
+
+        for pixel in image.pixels().filter(|pixel| pixel.0[3] >= 128) {
+            let color = [pixel.0[0], pixel.0[1], pixel.0[2]];
+
+            if let Entry::Vacant(entry) = indices.entry(color) {
+                if colors.len() == MAX_COLORS {
+                    return Self::cube();
+                }
+
+                entry.insert(colors.len());
+                colors.push(color);
+            }
+        }
+
+        Self {
+            colors,
+            indices: Some(indices),
+        }
+    }
+
+    fn cube() -> Self {
+        let level = |index: usize| (index * 51) as u8;
+
+        Self {
+            colors: (0..216)
+                .map(|index| [level(index / 36), level(index / 6 % 6), level(index % 6)])
+                .collect(),
+            indices: None,
+        }
+    }
+
+    fn index_of(&self, color: [u8; 3]) -> usize {
+        match &self.indices {
+            Some(indices) => indices[&color],
+            None => {
+                let level = |channel: u8| (channel as usize + 25) / 51;
+                level(color[0]) * 36 + level(color[1]) * 6 + level(color[2])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+    #[test]
+    fn two_colors() {
+        let mut image = RgbaImage::new(3, 2);
+        image.put_pixel(0, 0, RED);
+        image.put_pixel(1, 0, RED);
+        image.put_pixel(1, 1, RED);
+        image.put_pixel(2, 1, BLUE);
+        image.put_pixel(2, 0, Rgba([0, 255, 0, 127]));
+
+        assert_eq!(
+            String::from_utf8(encode_sixel(&image)).unwrap(),
+            "\x1bP0;1;0q\"1;1;3;2#0;2;100;0;0#1;2;0;0;100#0@B$#1??A-\x1b\\"
+        );
+    }
+
+    #[test]
+    fn bands_are_six_rows_tall() {
+        let mut image = RgbaImage::new(1, 7);
+        image.put_pixel(0, 5, RED);
+        image.put_pixel(0, 6, RED);
+
+        assert_eq!(
+            String::from_utf8(encode_sixel(&image)).unwrap(),
+            "\x1bP0;1;0q\"1;1;1;7#0;2;100;0;0#0_-#0@-\x1b\\"
+        );
+    }
+
+    #[test]
+    fn repeated_sixels_are_run_length_encoded() {
+        let mut bytes = Vec::new();
+        write_sixels(&mut bytes, &[1, 1, 1, 1, 2, 2, 2, 0, 3, 0, 0]);
+
+        assert_eq!(bytes, b"!4@AAA?B");
+    }
+
+    #[test]
+    fn too_many_colors_fall_back_to_the_color_cube() {
+        let image = |width: u32| {
+            RgbaImage::from_fn(width, 1, |x, _| Rgba([x as u8, (x / 256) as u8, 0, 255]))
+        };
+
+        let palette = Palette::new(&image(256));
+        assert_eq!(palette.colors.len(), 256);
+        assert_eq!(palette.index_of([255, 0, 0]), 255);
+
+        let palette = Palette::new(&image(257));
+        assert_eq!(palette.colors.len(), 216);
+        assert_eq!(palette.colors[215], [255, 255, 255]);
+        assert_eq!(palette.index_of([255, 128, 0]), 198);
+        assert_eq!(palette.index_of([0, 1, 0]), 0);
+
+        let sixel = String::from_utf8(encode_sixel(&image(257))).unwrap();
+        assert!(sixel.contains("#215;2;100;100;100#0"));
+        assert!(!sixel.contains("#216"));
+    }
+}
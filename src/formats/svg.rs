@@ -0,0 +1,152 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    font::BunnyFont,
+    formats::html::{css_color, escape_xml, orientation_css_matrix},
+    grid::BunnyGrid,
+    traits::{color::Color, source_image::SourceImage},
+};
+
+//Exports a grid as an SVG drawing every glyph from the font's atlas at the given url
+//Each glyph used gets a mask cut out of the atlas with get_src_uvs, which cells fill with their foreground colour
+pub fn export_svg<T, C>(
+    grid: &BunnyGrid<C>,
+    font: &BunnyFont<T>,
+    atlas_url: &str,
+    scale: usize,
+) -> String
+where
+    T: SourceImage,
+    C: Color,
+{
+    let (width, height) = grid.dimensions();
+    let (char_width, char_height) = font.char_dimensions();
+    let (texture_width, texture_height) = font.texture().get_pixel_dimensions();
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+         width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\" image-rendering=\"pixelated\">\n",
+        width * char_width * scale,
+        height * char_height * scale,
+        width * char_width,
+        height * char_height,
+    );
+
+    let glyphs: BTreeSet<usize> = grid
+        .cells()
+        .filter_map(|(_, cell)| cell.map(|bunny_char| bunny_char.index))
+        .collect();
+
+    svg += "<defs>\n";
+    svg += &format!(
+        "<image id=\"atlas\" width=\"{}\" height=\"{}\" xlink:href=\"{}\" href=\"{}\"/>\n",
+        texture_width,
+        texture_height,
+        escape_xml(atlas_url),
+        escape_xml(atlas_url),
+    );
+
+    //Masks are in the coordinates of a single upright char, with the atlas shifted so the glyph sits at the origin
+    for index in glyphs {
+        let (src_x, src_y, _, _) = font.get_src_uvs(index);
+
+        svg += &format!(
+            "<mask id=\"glyph-{}\" maskUnits=\"userSpaceOnUse\" x=\"0\" y=\"0\" width=\"{}\" height=\"{}\">\
+             <use xlink:href=\"#atlas\" href=\"#atlas\" x=\"-{}\" y=\"-{}\"/></mask>\n",
+            index,
+            char_width,
+            char_height,
+            (src_x * texture_width as f32).round(),
+            (src_y * texture_height as f32).round(),
+        );
+    }
+
+    svg += "</defs>\n";
+
+    for ((x, y), cell) in grid.cells() {
+        let bunny_char = match cell {
+            Some(bunny_char) => bunny_char,
+            None => continue,
+        };
+
+        let (cell_x, cell_y) = (x * char_width, y * char_height);
+
+        if let Some(background) = bunny_char.background {
+            svg += &format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                cell_x,
+                cell_y,
+                char_width,
+                char_height,
+                css_color(background)
+            );
+        }
+
+        //Rotation and mirroring happen around the centre of the cell
+        let transform = match orientation_css_matrix(bunny_char) {
+            Some([a, b, c, d]) => format!(
+                "translate({} {}) matrix({} {} {} {} 0 0) translate({} {})",
+                cell_x as f32 + char_width as f32 / 2.0,
+                cell_y as f32 + char_height as f32 / 2.0,
+                a,
+                b,
+                c,
+                d,
+                -(char_width as f32) / 2.0,
+                -(char_height as f32) / 2.0,
+            ),
+            None => format!("translate({} {})", cell_x, cell_y),
+        };
+
+        svg += &format!(
+            "<rect transform=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" mask=\"url(#glyph-{})\"/>\n",
+            transform,
+            char_width,
+            char_height,
+            css_color(bunny_char.foreground),
+            bunny_char.index
+        );
+    }
+
+    svg += "</svg>\n";
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        char::BunnyChar,
+        char_transforms::{CharMirror, CharRotation},
+        traits::{color::TestColor, source_image::TestImage},
+    };
+
+    #[test]
+    fn each_glyph_gets_one_mask() {
+        let font = BunnyFont::new(TestImage((128, 64)), (8, 8));
+        let cell = BunnyChar::new(
+            0x41,
+            TestColor([0, 0, 255, 255]),
+            None,
+            CharRotation::None,
+            CharMirror::None,
+        );
+
+        let mut grid = BunnyGrid::new((2, 1));
+        grid.set(0, 0, Some(cell.background(Some(TestColor([0, 0, 0, 255])))));
+        grid.set(1, 0, Some(cell.rotation(CharRotation::Rotation90)));
+
+        let svg = export_svg(&grid, &font, "a&b.png", 3);
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains("width=\"48\" height=\"24\" viewBox=\"0 0 16 8\""));
+        assert!(svg.contains("href=\"a&amp;b.png\""));
+        assert_eq!(svg.matches("<mask ").count(), 1);
+        assert!(svg.contains("<use xlink:href=\"#atlas\" href=\"#atlas\" x=\"-8\" y=\"-32\"/>"));
+        assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"8\" height=\"8\" fill=\"#000000\"/>"));
+        assert!(svg.contains(
+            "<rect transform=\"translate(12 4) matrix(0 1 -1 0 0 0) translate(-4 -4)\" \
+             width=\"8\" height=\"8\" fill=\"#0000ff\" mask=\"url(#glyph-65)\"/>"
+        ));
+    }
+}
@@ -0,0 +1,391 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use image::{Rgba, RgbaImage};
+
+use crate::{
+    char::BunnyChar,
+    char_transforms::{CharMirror, CharRotation},
+    formats::{ansi::VGA_PALETTE, invalid_data, ByteReader},
+    grid::BunnyGrid,
+    integrations::image::ImageBunnyFont,
+    traits::color::Color,
+};
+
+//XBin files are laid out as
+//
+//  "XBIN" 0x1a, u16 width, u16 height, u8 font height, u8 flags
+//  an optional palette of 16 colours with 6 bit channels
+//  an optional font of 256 or 512 glyphs, 8 pixels wide with one byte per row
+//  the cells as pairs of glyph and attribute bytes, optionally run length compressed
+//
+//The low nibble of an attribute is the foreground colour and the high nibble the background colour
+
+const XBIN_ID: &[u8; 5] = b"XBIN\x1a";
+
+const PALETTE_FLAG: u8 = 1 << 0;
+const FONT_FLAG: u8 = 1 << 1;
+const COMPRESS_FLAG: u8 = 1 << 2;
+const NON_BLINK_FLAG: u8 = 1 << 3;
+const FONT_512_FLAG: u8 = 1 << 4;
+
+const FONT_CHAR_WIDTH: usize = 8;
+//Embedded fonts are laid out 16 glyphs to a row
+const FONT_COLUMNS: usize = 16;
+
+//Attributes number colours in IBM order, which swaps blue and red compared to the SGR order of the VGA palette
+const IBM_COLOR_ORDER: [usize; 16] = [0, 4, 2, 6, 1, 5, 3, 7, 8, 12, 10, 14, 9, 13, 11, 15];
+
+pub struct XBinImage<C> {
+    pub grid: BunnyGrid<C>,
+    //The embedded font if the file has one, otherwise the art is meant to be drawn with the standard VGA font
+    pub font: Option<ImageBunnyFont>,
+    pub palette: [C; 16],
+    //Whether bright backgrounds are used instead of blinking
+    pub ice_colors: bool,
+}
+
+impl<C> XBinImage<C>
+where
+    C: Color,
+{
+    pub fn load<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::parse(&fs::read(path)?)
+    }
+
+    pub fn read<R>(mut reader: R) -> io::Result<Self>
+    where
+        R: Read,
+    {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        Self::parse(&bytes)
+    }
+
+    pub fn parse(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = ByteReader::new(bytes);
+
+        if reader.take(XBIN_ID.len())? != XBIN_ID {
+            return Err(invalid_data("not an XBin file"));
+        }
+
+        let width = reader.read_u16()? as usize;
+        let height = reader.read_u16()? as usize;
+        let font_height = reader.read_u8()? as usize;
+        let flags = reader.read_u8()?;
+
+        let palette = if flags & PALETTE_FLAG != 0 {
+            let mut palette = [C::from_rgba8([0, 0, 0, 255]); 16];
+
+            for (color, channels) in palette.iter_mut().zip(reader.take(48)?.chunks_exact(3)) {
+                //Channels are 6 bit, the top bits are repeated at the bottom so that 63 becomes 255
+                let channel = |value: u8| (value & 0x3f) << 2 | (value & 0x3f) >> 4;
+                *color = C::from_rgba8([
+                    channel(channels[0]),
+                    channel(channels[1]),
+                    channel(channels[2]),
+                    255,
+                ]);
+            }
+
+            palette
+        } else {
+            IBM_COLOR_ORDER.map(|index| {
+                let [r, g, b] = VGA_PALETTE[index];
+                C::from_rgba8([r, g, b, 255])
+            })
+        };
+
+        let font_512 = flags & FONT_512_FLAG != 0;
+        let glyph_count = if font_512 { 512 } else { 256 };
+
+        let font = if flags & FONT_FLAG != 0 {
+            if font_height == 0 || font_height > 32 {
+                return Err(invalid_data("invalid font height"));
+            }
+
+            Some(read_font(
+                reader.take(glyph_count * font_height)?,
+                glyph_count,
+                font_height,
+            ))
+        } else {
+            None
+        };
+
+        let cells = if flags & COMPRESS_FLAG != 0 {
+            decompress(&mut reader, width * height)?
+        } else {
+            reader.take(width * height * 2)?.to_vec()
+        };
+
+        let ice_colors = flags & NON_BLINK_FLAG != 0;
+        let mut grid = BunnyGrid::new((width, height));
+
+        for (index, cell) in cells.chunks_exact(2).enumerate() {
+            let (glyph, attribute) = (cell[0] as usize, cell[1]);
+
+            //512 glyph fonts use the bright bit of the foreground to pick the upper half of the font
+            let (glyph, foreground) = if font_512 {
+                let bank = if attribute & 0x08 != 0 { 256 } else { 0 };
+                (bank + glyph, attribute & 0x07)
+            } else {
+                (glyph, attribute & 0x0f)
+            };
+
+            //Without iCE colours the top bit means blinking, which is left out
+            let background = if ice_colors {
+                attribute >> 4
+            } else {
+                attribute >> 4 & 0x07
+            };
+
+            grid.set(
+                index % width,
+                index / width,
+                Some(BunnyChar::new(
+                    glyph,
+                    palette[foreground as usize],
+                    Some(palette[background as usize]),
+                    CharRotation::None,
+                    CharMirror::None,
+                )),
+            );
+        }
+
+        Ok(Self {
+            grid,
+            font,
+            palette,
+            ice_colors,
+        })
+    }
+}
+
+//Draws the glyphs of an embedded font as white on transparent, which BunnyFont tints with each cell's colours
+fn read_font(bytes: &[u8], glyph_count: usize, font_height: usize) -> ImageBunnyFont {
+    let rows = glyph_count / FONT_COLUMNS;
+    let mut texture = RgbaImage::new(
+        (FONT_COLUMNS * FONT_CHAR_WIDTH) as u32,
+        (rows * font_height) as u32,
+    );
+
+    for (glyph, glyph_rows) in bytes.chunks_exact(font_height).enumerate() {
+        let (glyph_x, glyph_y) = (
+            glyph % FONT_COLUMNS * FONT_CHAR_WIDTH,
+            glyph / FONT_COLUMNS * font_height,
+        );
+
+        for (y, row) in glyph_rows.iter().enumerate() {
+            for x in 0..FONT_CHAR_WIDTH {
+                if row & (0x80 >> x) != 0 {
+                    texture.put_pixel((glyph_x + x) as u32, (glyph_y + y) as u32, Rgba([255; 4]));
+                }
+            }
+        }
+    }
+
+    ImageBunnyFont::new(texture, (FONT_CHAR_WIDTH, font_height))
+}
+
+//Expands run length compressed cells into glyph and attribute pairs
+//Each run starts with a byte whose top two bits give what is repeated and whose low six bits give the length minus one
+fn decompress(reader: &mut ByteReader, cell_count: usize) -> io::Result<Vec<u8>> {
+    let mut cells = Vec::with_capacity(cell_count * 2);
+
+    while cells.len() < cell_count * 2 {
+        let run = reader.read_u8()?;
+        let length = (run & 0x3f) as usize + 1;
+
+        match run >> 6 {
+            //Nothing repeated
+            0 => cells.extend_from_slice(reader.take(length * 2)?),
+            //The glyph is repeated
+            1 => {
+                let glyph = reader.read_u8()?;
+
+                for attribute in reader.take(length)? {
+                    cells.extend_from_slice(&[glyph, *attribute]);
+                }
+            }
+            //The attribute is repeated
+            2 => {
+                let attribute = reader.read_u8()?;
+
+                for glyph in reader.take(length)? {
+                    cells.extend_from_slice(&[*glyph, attribute]);
+                }
+            }
+            //Both are repeated
+            _ => {
+                let cell = reader.take(2)?;
+
+                for _ in 0..length {
+                    cells.extend_from_slice(cell);
+                }
+            }
+        }
+    }
+
+    //Runs aren't meant to cross the end of the image, anything past it is dropped
+    cells.truncate(cell_count * 2);
+    Ok(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::color::TestColor;
+
+    fn header(dimensions: (u16, u16), font_height: u8, flags: u8) -> Vec<u8> {
+        let mut bytes = XBIN_ID.to_vec();
+        bytes.extend(dimensions.0.to_le_bytes());
+        bytes.extend(dimensions.1.to_le_bytes());
+        bytes.extend([font_height, flags]);
+        bytes
+    }
+
+    fn parse(bytes: &[u8]) -> io::Result<XBinImage<TestColor>> {
+        XBinImage::parse(bytes)
+    }
+
+    //Each cell as its glyph and its colours
+    fn cells(image: &XBinImage<TestColor>) -> Vec<(usize, [u8; 4], [u8; 4])> {
+        image
+            .grid
+            .cells()
+            .map(|(_, cell)| {
+                let cell = cell.unwrap();
+                (cell.index, cell.foreground.0, cell.background.unwrap().0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn blinking_backgrounds_are_left_out_without_ice_colours() {
+        let cells_with = |flags| {
+            let mut bytes = header((2, 1), 16, flags);
+            bytes.extend([0x41, 0x1f, 0x42, 0x9c]);
+            cells(&parse(&bytes).unwrap())
+        };
+
+        assert_eq!(
+            cells_with(0),
+            vec![
+                (0x41, [255, 255, 255, 255], [0, 0, 170, 255]),
+                (0x42, [255, 85, 85, 255], [0, 0, 170, 255]),
+            ]
+        );
+        assert_eq!(cells_with(NON_BLINK_FLAG)[1].2, [85, 85, 255, 255]);
+    }
+
+    #[test]
+    fn palettes_have_6_bit_channels() {
+        let mut bytes = header((1, 1), 16, PALETTE_FLAG);
+        let mut palette = [0; 48];
+        palette[3..6].copy_from_slice(&[63, 0x20, 0]);
+        bytes.extend(palette);
+        bytes.extend([0x41, 0x01]);
+
+        let image = parse(&bytes).unwrap();
+
+        assert_eq!(image.palette[1], TestColor([255, 130, 0, 255]));
+        assert_eq!(
+            cells(&image),
+            vec![(0x41, [255, 130, 0, 255], [0, 0, 0, 255])]
+        );
+    }
+
+    #[test]
+    fn embedded_fonts_are_drawn_white_on_transparent() {
+        let mut bytes = header((1, 1), 2, FONT_FLAG);
+        let mut font = [0; 512];
+        font[2..4].copy_from_slice(&[0x80, 0x01]);
+        bytes.extend(font);
+        bytes.extend([0x01, 0x07]);
+
+        let font = parse(&bytes).unwrap().font.unwrap();
+        let texture = font.texture();
+
+        assert_eq!(font.charset_dimensions(), (16, 16));
+        assert_eq!(font.char_dimensions(), (8, 2));
+        assert_eq!(texture.get_pixel(8, 0), &Rgba([255; 4]));
+        assert_eq!(texture.get_pixel(15, 1), &Rgba([255; 4]));
+        assert_eq!(texture.get_pixel(9, 0), &Rgba([0; 4]));
+    }
+
+    #[test]
+    fn fonts_of_512_glyphs_use_the_bright_bit() {
+        let mut bytes = header((2, 1), 1, FONT_FLAG | FONT_512_FLAG);
+        bytes.extend([0; 512]);
+        bytes.extend([0x41, 0x0f, 0x41, 0x07]);
+
+        let image = parse(&bytes).unwrap();
+        let glyphs: Vec<(usize, [u8; 4])> = cells(&image)
+            .into_iter()
+            .map(|(glyph, foreground, _)| (glyph, foreground))
+            .collect();
+
+        assert_eq!(
+            glyphs,
+            vec![(0x141, [170, 170, 170, 255]), (0x41, [170, 170, 170, 255])]
+        );
+        assert_eq!(image.font.unwrap().charset_dimensions(), (16, 32));
+    }
+
+    #[test]
+    fn compressed_runs() {
+        let mut bytes = header((5, 1), 16, COMPRESS_FLAG);
+        //Both repeated twice, the glyph repeated once, the attribute repeated once, then nothing repeated
+        bytes.extend([0xc1, 0x41, 0x07]);
+        bytes.extend([0x40, 0x42, 0x0e]);
+        bytes.extend([0x80, 0x01, 0x43]);
+        //This run goes past the end of the image, so only its first cell is kept
+        bytes.extend([0x01, 0x44, 0x02, 0x45, 0x03]);
+
+        let glyphs: Vec<(usize, [u8; 4])> = cells(&parse(&bytes).unwrap())
+            .into_iter()
+            .map(|(glyph, foreground, _)| (glyph, foreground))
+            .collect();
+
+        assert_eq!(
+            glyphs,
+            vec![
+                (0x41, [170, 170, 170, 255]),
+                (0x41, [170, 170, 170, 255]),
+                (0x42, [255, 255, 85, 255]),
+                (0x43, [0, 0, 170, 255]),
+                (0x44, [0, 170, 0, 255]),
+            ]
+        );
+    }
+
+    #[test]
+    fn corrupt_files() {
+        let kind = |bytes: &[u8]| parse(bytes).err().map(|error| error.kind());
+
+        assert_eq!(
+            kind(b"XBIM\x1a\x01\x00\x01\x00\x10\x00"),
+            Some(io::ErrorKind::InvalidData)
+        );
+        assert_eq!(
+            kind(&header((2, 1), 16, 0)),
+            Some(io::ErrorKind::UnexpectedEof)
+        );
+        assert_eq!(
+            kind(&header((1, 1), 0, FONT_FLAG)),
+            Some(io::ErrorKind::InvalidData)
+        );
+        assert_eq!(
+            kind(&[header((4, 1), 16, COMPRESS_FLAG), vec![0xc1, 0x41]].concat()),
+            Some(io::ErrorKind::UnexpectedEof)
+        );
+    }
+}
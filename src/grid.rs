@@ -0,0 +1,369 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{char::BunnyChar, traits::color::Color};
+
+//A rectangular grid of optional chars that remembers which cells changed since it was last presented
+#[derive(Clone, Debug)]
+pub struct BunnyGrid<C> {
+    width: usize,
+    height: usize,
+    cells: Vec<Option<BunnyChar<C>>>,
+    dirty: Vec<bool>,
+}
+
+impl<C> BunnyGrid<C>
+where
+    C: Color,
+{
+    pub fn new(dimensions: (usize, usize)) -> Self {
+        let (width, height) = dimensions;
+
+        Self {
+            width,
+            height,
+            cells: vec![None; width * height],
+            dirty: vec![true; width * height],
+        }
+    }
+
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    fn cell_index(&self, x: usize, y: usize) -> usize {
+        assert!(
+            x < self.width && y < self.height,
+            "cell ({}, {}) is out of bounds for grid of size ({}, {})",
+            x,
+            y,
+            self.width,
+            self.height,
+        );
+
+        y * self.width + x
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&BunnyChar<C>> {
+        self.cells[self.cell_index(x, y)].as_ref()
+    }
+
+    //Sets a cell, only marking it dirty if its contents actually changed
+    pub fn set(&mut self, x: usize, y: usize, cell: Option<BunnyChar<C>>) {
+        let index = self.cell_index(x, y);
+
+        if self.cells[index] != cell {
+            self.cells[index] = cell;
+            self.dirty[index] = true;
+        }
+    }
+
+    //Like set, but silently ignores cells outside of the grid
+    pub fn set_clipped(&mut self, x: i32, y: i32, cell: Option<BunnyChar<C>>) {
+        if self.in_bounds(x, y) {
+            self.set(x as usize, y as usize, cell);
+        }
+    }
+
+    pub fn fill(&mut self, cell: Option<BunnyChar<C>>) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.set(x, y, cell);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.fill(None);
+    }
+
+    //Resizes the grid, keeping the overlapping cells in place and marking everything dirty
+    pub fn resize(&mut self, dimensions: (usize, usize)) {
+        let mut resized = Self::new(dimensions);
+
+        for y in 0..self.height.min(resized.height) {
+            for x in 0..self.width.min(resized.width) {
+                let index = resized.cell_index(x, y);
+                resized.cells[index] = self.get(x, y).copied();
+            }
+        }
+
+        *self = resized;
+    }
+
+    //Iterates over every cell in row-major order as ((x, y), cell)
+    pub fn cells(&self) -> impl Iterator<Item = ((usize, usize), Option<&BunnyChar<C>>)> + '_ {
+        let width = self.width;
+
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(index, cell)| ((index % width, index / width), cell.as_ref()))
+    }
+
+    pub fn is_dirty(&self, x: usize, y: usize) -> bool {
+        self.dirty[self.cell_index(x, y)]
+    }
+
+    pub fn has_dirty_cells(&self) -> bool {
+        self.dirty.iter().any(|dirty| *dirty)
+    }
+
+    //Iterates over the cells that changed since the last call to mark_clean
+    pub fn dirty_cells(&self) -> impl Iterator<Item = ((usize, usize), Option<&BunnyChar<C>>)> + '_ {
+        self.cells()
+            .zip(self.dirty.iter())
+            .filter(|(_, dirty)| **dirty)
+            .map(|(cell, _)| cell)
+    }
+
+    pub fn mark_dirty(&mut self, x: usize, y: usize) {
+        let index = self.cell_index(x, y);
+        self.dirty[index] = true;
+    }
+
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty.iter_mut().for_each(|dirty| *dirty = true);
+    }
+
+    //Should be called by a backend once it has presented the grid
+    pub fn mark_clean(&mut self) {
+        self.dirty.iter_mut().for_each(|dirty| *dirty = false);
+    }
+
+    //Collects the dirty cells into a diff and marks the grid clean
+    pub fn take_diff(&mut self) -> GridDiff<C> {
+        let changes = self
+            .dirty_cells()
+            .map(|(pos, cell)| CellChange {
+                pos,
+                cell: cell.copied(),
+            })
+            .collect();
+
+        self.mark_clean();
+
+        GridDiff {
+            dimensions: self.dimensions(),
+            changes,
+        }
+    }
+
+    //Lists the cells that differ between two grids, a resized grid counts as entirely changed
+    pub fn diff(old: &Self, new: &Self) -> GridDiff<C> {
+        let changes = if old.dimensions() == new.dimensions() {
+            old.cells()
+                .zip(new.cells())
+                .filter(|((_, old_cell), (_, new_cell))| old_cell != new_cell)
+                .map(|(_, (pos, cell))| CellChange {
+                    pos,
+                    cell: cell.copied(),
+                })
+                .collect()
+        } else {
+            new.cells()
+                .map(|(pos, cell)| CellChange {
+                    pos,
+                    cell: cell.copied(),
+                })
+                .collect()
+        };
+
+        GridDiff {
+            dimensions: new.dimensions(),
+            changes,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CellChange<C> {
+    pub pos: (usize, usize),
+    pub cell: Option<BunnyChar<C>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GridDiff<C> {
+    pub dimensions: (usize, usize),
+    pub changes: Vec<CellChange<C>>,
+}
+
+impl<C> GridDiff<C>
+where
+    C: Color,
+{
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    //Applies the diff to a grid, resizing it first if the dimensions don't match
+    pub fn apply(&self, grid: &mut BunnyGrid<C>) {
+        if grid.dimensions() != self.dimensions {
+            grid.resize(self.dimensions);
+        }
+
+        for change in &self.changes {
+            let (x, y) = change.pos;
+            grid.set(x, y, change.cell);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        char_transforms::{CharMirror, CharRotation},
+        traits::color::TestColor,
+    };
+
+    fn cell(index: usize) -> Option<BunnyChar<TestColor>> {
+        Some(BunnyChar::new(
+            index,
+            TestColor([255; 4]),
+            None,
+            CharRotation::None,
+            CharMirror::None,
+        ))
+    }
+
+    fn clean_grid(dimensions: (usize, usize)) -> BunnyGrid<TestColor> {
+        let mut grid = BunnyGrid::new(dimensions);
+        grid.mark_clean();
+        grid
+    }
+
+    #[test]
+    fn new_grid_is_dirty() {
+        let grid: BunnyGrid<TestColor> = BunnyGrid::new((2, 2));
+
+        assert!(grid.has_dirty_cells());
+        assert_eq!(grid.dirty_cells().count(), 4);
+    }
+
+    #[test]
+    fn set_only_marks_changed_cells_dirty() {
+        let mut grid = clean_grid((3, 2));
+
+        grid.set(1, 1, None);
+        assert!(!grid.has_dirty_cells());
+
+        grid.set(1, 1, cell(5));
+        assert!(grid.is_dirty(1, 1));
+        assert!(!grid.is_dirty(0, 1));
+
+        grid.mark_clean();
+        grid.set(1, 1, cell(5));
+        assert!(!grid.has_dirty_cells());
+    }
+
+    #[test]
+    fn set_clipped_ignores_cells_outside() {
+        let mut grid = clean_grid((2, 2));
+
+        grid.set_clipped(-1, 0, cell(1));
+        grid.set_clipped(2, 1, cell(1));
+        assert!(!grid.has_dirty_cells());
+
+        grid.set_clipped(1, 1, cell(1));
+        assert_eq!(grid.get(1, 1), cell(1).as_ref());
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn get_outside_panics() {
+        clean_grid((2, 2)).get(2, 0);
+    }
+
+    #[test]
+    fn take_diff_collects_dirty_cells_and_cleans() {
+        let mut grid = clean_grid((3, 3));
+        grid.set(2, 0, cell(1));
+        grid.set(0, 2, cell(2));
+
+        let diff = grid.take_diff();
+
+        assert_eq!(diff.dimensions, (3, 3));
+        assert_eq!(
+            diff.changes,
+            vec![
+                CellChange {
+                    pos: (2, 0),
+                    cell: cell(1),
+                },
+                CellChange {
+                    pos: (0, 2),
+                    cell: cell(2),
+                },
+            ]
+        );
+        assert!(!grid.has_dirty_cells());
+        assert!(grid.take_diff().is_empty());
+    }
+
+    #[test]
+    fn diff_lists_changed_cells() {
+        let old = clean_grid((2, 2));
+        let mut new = old.clone();
+        new.set(1, 0, cell(3));
+
+        let diff = BunnyGrid::diff(&old, &new);
+
+        assert_eq!(
+            diff.changes,
+            vec![CellChange {
+                pos: (1, 0),
+                cell: cell(3),
+            }]
+        );
+    }
+
+    #[test]
+    fn diff_across_resize_lists_every_cell() {
+        let old = clean_grid((2, 2));
+        let mut new = clean_grid((3, 1));
+        new.set(2, 0, cell(1));
+
+        let diff = BunnyGrid::diff(&old, &new);
+
+        assert_eq!(diff.dimensions, (3, 1));
+        assert_eq!(diff.changes.len(), 3);
+        assert_eq!(diff.changes[2].cell, cell(1));
+    }
+
+    #[test]
+    fn apply_brings_grid_in_sync() {
+        let mut old = clean_grid((2, 2));
+        old.set(0, 0, cell(1));
+
+        let mut new = clean_grid((3, 2));
+        new.set(2, 1, cell(2));
+
+        let mut synced = old.clone();
+        BunnyGrid::diff(&old, &new).apply(&mut synced);
+
+        assert_eq!(synced.dimensions(), (3, 2));
+        assert!(BunnyGrid::diff(&synced, &new).is_empty());
+        assert!(synced.is_dirty(2, 1));
+    }
+
+    #[test]
+    fn resize_keeps_overlapping_cells() {
+        let mut grid = clean_grid((3, 3));
+        grid.set(1, 1, cell(1));
+        grid.set(2, 2, cell(2));
+
+        grid.resize((2, 4));
+
+        assert_eq!(grid.dimensions(), (2, 4));
+        assert_eq!(grid.get(1, 1), cell(1).as_ref());
+        assert_eq!(grid.get(1, 3), None);
+        assert_eq!(grid.dirty_cells().count(), 8);
+    }
+}
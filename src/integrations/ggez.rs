@@ -1,23 +1,36 @@
 use ggez::{
     graphics::{
-        spritebatch::SpriteBatch, BlendMode, Color as GgColor, DrawParam, Drawable, FilterMode,
-        Image as GgImage, Rect,
+        self, spritebatch::SpriteBatch, BlendMode, Color as GgColor, DrawParam, Drawable,
+        FilterMode, Image as GgImage, Rect, Transform,
     },
     mint, Context, GameResult,
 };
-use glam::f32::{mat2, vec2, Affine2};
+use glam::f32::{mat2, vec2, vec3, Affine2, Mat4};
 
 use crate::{
     char::BunnyChar,
     char_transforms::{CharMirror, CharRotation},
-    font::BunnyFont,
+    font::{BunnyFont, BACKGROUND_CHAR_INDEX},
+    grid::BunnyGrid,
     traits::{color::Color, source_image::SourceImage},
+    viewport::Viewport,
 };
 
 pub type GgBunnyFont = BunnyFont<GgImage>;
 pub type GgBunnyChar = BunnyChar<GgColor>;
+pub type GgBunnyGrid = BunnyGrid<GgColor>;
 
-impl Color for GgColor {}
+impl Color for GgColor {
+    fn from_rgba8(rgba: [u8; 4]) -> Self {
+        let [r, g, b, a] = rgba;
+        GgColor::from_rgba(r, g, b, a)
+    }
+
+    fn to_rgba8(&self) -> [u8; 4] {
+        let (r, g, b, a) = self.to_rgba();
+        [r, g, b, a]
+    }
+}
 
 impl SourceImage for GgImage {
     type Color = GgColor;
@@ -70,6 +83,8 @@ impl CharMirror {
 pub struct GgBunnyFontBatch {
     font: GgBunnyFont,
     batch: SpriteBatch,
+    //The scaling the batch holds a whole grid at, None when it has to be rebuilt by the next draw_grid
+    grid_scaling: Option<f32>,
 }
 
 impl GgBunnyFontBatch {
@@ -79,7 +94,11 @@ impl GgBunnyFontBatch {
         batch.set_filter(FilterMode::Nearest);
         batch.set_blend_mode(Some(BlendMode::Alpha));
 
-        Ok(Self { batch, font })
+        Ok(Self {
+            batch,
+            font,
+            grid_scaling: None,
+        })
     }
 
     pub fn font(&self) -> &GgBunnyFont {
@@ -95,10 +114,105 @@ impl GgBunnyFontBatch {
 
     pub fn clear(&mut self) {
         self.batch.clear();
+        self.grid_scaling = None;
+    }
+
+    //Rebuilds the batch from a grid, skipping the rebuild entirely if no cells changed since the last call
+    //and the batch still holds the grid at the same scaling
+    pub fn draw_grid(&mut self, grid: &mut GgBunnyGrid, scaling: f32) {
+        if !grid.has_dirty_cells() && self.grid_scaling == Some(scaling) {
+            return;
+        }
+
+        self.clear();
+
+        for ((x, y), cell) in grid.cells() {
+            if let Some(bunny_char) = cell {
+                bunny_char.draw_to_font_batch(self, (x as i32, y as i32), scaling);
+            }
+        }
+
+        grid.mark_clean();
+        self.grid_scaling = Some(scaling);
+    }
+
+    //Converts a position in screen coordinates to the cell under it, given the scaling the grid was
+    //added at and the DrawParam the batch is drawn with, returns None outside of the grid
+    pub fn pick_cell(
+        &self,
+        ctx: &mut Context,
+        point: (f32, f32),
+        scaling: f32,
+        param: DrawParam,
+        grid_dimensions: (usize, usize),
+    ) -> Option<(usize, usize)> {
+        let mut param = param;
+
+        //Sprite batches scale a nonzero draw offset by the bounding box of their sprites, which
+        //isn't the grid when it has empty cells around its edges, so the offset is scaled the same way here
+        if let Transform::Values { offset, .. } = param.trans {
+            if offset != [0.0, 0.0].into() {
+                if let Some(dimensions) = self.batch.dimensions(ctx) {
+                    param = param.offset(mint::Point2 {
+                        x: offset.x * dimensions.w + dimensions.x,
+                        y: offset.y * dimensions.h + dimensions.y,
+                    });
+                }
+            }
+        }
+
+        let transform = Mat4::from(param.trans.to_bare_matrix());
+
+        if transform.determinant() == 0.0 {
+            return None;
+        }
+
+        let local = transform
+            .inverse()
+            .transform_point3(vec3(point.0, point.1, 0.0));
+
+        self.font
+            .pick_cell((local.x, local.y), scaling, (0.0, 0.0), grid_dimensions)
+    }
+
+    //Converts a position in screen coordinates to the world cell under it, for a batch built with draw_viewport
+    pub fn pick_world_cell(
+        &self,
+        ctx: &mut Context,
+        point: (f32, f32),
+        scaling: f32,
+        param: DrawParam,
+        viewport: &Viewport,
+    ) -> Option<(i32, i32)> {
+        let screen_dimensions = (viewport.screen.right(), viewport.screen.bottom());
+
+        self.pick_cell(ctx, point, scaling, param, screen_dimensions)
+            .and_then(|(x, y)| viewport.screen_to_world(x, y))
+    }
+
+    //Adds only the world cells visible through a viewport to the batch, at their screen positions
+    pub fn draw_viewport(&mut self, world: &GgBunnyGrid, viewport: &Viewport, scaling: f32) {
+        //The batch no longer holds just a grid, so the next draw_grid has to rebuild it
+        self.grid_scaling = None;
+
+        for ((world_x, world_y), (screen_x, screen_y)) in viewport.visible_cells() {
+            if let Some(bunny_char) = world.get(world_x, world_y) {
+                bunny_char.draw_to_font_batch(self, (screen_x as i32, screen_y as i32), scaling);
+            }
+        }
     }
 }
 
-const BACKGROUND_CHAR_INDEX: usize = 0x2c7;
+//Mouse events are in window pixels, this converts them to the screen coordinates things are drawn in
+pub fn window_to_screen(ctx: &Context, point: (f32, f32)) -> (f32, f32) {
+    let screen = graphics::screen_coordinates(ctx);
+    let (window_width, window_height) = graphics::drawable_size(ctx);
+
+    (
+        screen.x + point.0 * screen.w / window_width,
+        screen.y + point.1 * screen.h / window_height,
+    )
+}
 
 impl GgBunnyChar {
     pub fn draw_to_font_batch(&self, batch: &mut GgBunnyFontBatch, dest: (i32, i32), scaling: f32) {
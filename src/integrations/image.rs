@@ -1,15 +1,32 @@
-use image::{RgbaImage, Rgba};
+use std::io::Cursor;
+
+use image::{ImageOutputFormat, ImageResult, Rgba, RgbaImage};
 
 use crate::{
     char::BunnyChar,
-    font::BunnyFont,
-    traits::{color::Color, into_scalar::IntoScalar, lerpable::Lerpable, pixel_indexable::PixelIndexable, source_image::SourceImage},
+    char_transforms::{CharMirror, CharRotation},
+    font::{BunnyFont, BACKGROUND_CHAR_INDEX},
+    grid::BunnyGrid,
+    traits::{
+        color::Color, into_scalar::IntoScalar, lerpable::Lerpable, pixel_indexable::PixelIndexable,
+        source_image::SourceImage,
+    },
+    viewport::Viewport,
 };
 
 pub type ImageBunnyFont = BunnyFont<RgbaImage>;
 pub type ImageBunnyChar = BunnyChar<Rgba<u8>>;
+pub type ImageBunnyGrid = BunnyGrid<Rgba<u8>>;
+
+impl Color for Rgba<u8> {
+    fn from_rgba8(rgba: [u8; 4]) -> Self {
+        Rgba(rgba)
+    }
 
-impl Color for Rgba<u8> {}
+    fn to_rgba8(&self) -> [u8; 4] {
+        self.0
+    }
+}
 
 impl SourceImage for RgbaImage {
     type Color = Rgba<u8>;
@@ -32,19 +49,360 @@ impl Lerpable for Rgba<u8> {
             (a.0[1] as f32 * (1.0 - scalar) + b.0[1] as f32 * scalar) as u8,
             (a.0[2] as f32 * (1.0 - scalar) + b.0[2] as f32 * scalar) as u8,
             (a.0[3] as f32 * (1.0 - scalar) + b.0[3] as f32 * scalar) as u8,
-        ].into()
+        ]
+        .into()
     }
 }
 
 impl IntoScalar for Rgba<u8> {
     fn into_scalar(&self) -> f32 {
-        let scalar = (self.0[0] as f32 / 256.0 +
-        self.0[1] as f32 / 256.0 +
-        self.0[2] as f32 / 256.0 +
-        self.0[3] as f32 / 256.0) / 4.0;
+        let scalar = (self.0[0] as f32 / 256.0
+            + self.0[1] as f32 / 256.0
+            + self.0[2] as f32 / 256.0
+            + self.0[3] as f32 / 256.0)
+            / 4.0;
 
-        assert!(scalar >= 0.0 && scalar <= 1.0, "scalar is not within range 0.0..=1.0, value was {}", scalar);
+        assert!(
+            (0.0..=1.0).contains(&scalar),
+            "scalar is not within range 0.0..=1.0, value was {}",
+            scalar
+        );
 
         scalar
     }
-}
\ No newline at end of file
+}
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (index, byte)| {
+            group | (*byte as u32) << (16 - index * 8)
+        });
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(BASE64_CHARS[(group >> (18 - index * 6)) as usize & 0x3f] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+//Encodes an image as a png data url, for embedding a font's atlas in exported html and svg
+pub fn png_data_url(image: &RgbaImage) -> ImageResult<String> {
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)?;
+
+    Ok(format!("data:image/png;base64,{}", base64(&png)))
+}
+
+//Draws a colour over another with straight alpha, the way ggez blends sprites
+fn blend(under: Rgba<u8>, over: Rgba<u8>) -> Rgba<u8> {
+    let over_alpha = over.0[3] as f32 / 255.0;
+    let under_alpha = under.0[3] as f32 / 255.0 * (1.0 - over_alpha);
+    let alpha = over_alpha + under_alpha;
+
+    if alpha <= 0.0 {
+        return Rgba([0; 4]);
+    }
+
+    let channel = |index: usize| {
+        ((over.0[index] as f32 * over_alpha + under.0[index] as f32 * under_alpha) / alpha).round()
+            as u8
+    };
+
+    Rgba([
+        channel(0),
+        channel(1),
+        channel(2),
+        (alpha * 255.0).round() as u8,
+    ])
+}
+
+//Tints a texture pixel by multiplying it with a colour, the way ggez applies a sprite's colour
+fn tint(pixel: &Rgba<u8>, color: Rgba<u8>) -> Rgba<u8> {
+    let channel =
+        |index: usize| ((pixel.0[index] as u32 * color.0[index] as u32 + 127) / 255) as u8;
+
+    Rgba([channel(0), channel(1), channel(2), channel(3)])
+}
+
+impl ImageBunnyFont {
+    //Draws a char onto an image with its top left corner in the given cell, each font pixel becomes a square of scale pixels
+    //Like draw_to_font_batch, the background glyph is tinted with the background and the char's glyph is tinted with the foreground over it
+    //Atlases too small to have a background glyph get a solid background instead
+    pub fn render_char(
+        &self,
+        image: &mut RgbaImage,
+        bunny_char: &ImageBunnyChar,
+        cell: (usize, usize),
+        scale: u32,
+    ) {
+        let (char_width, char_height) = self.char_dimensions();
+        let scale = scale as usize;
+        let (origin_x, origin_y) = (cell.0 * char_width * scale, cell.1 * char_height * scale);
+
+        let (charset_width, charset_height) = self.charset_dimensions();
+        let background_char = bunny_char.index(BACKGROUND_CHAR_INDEX);
+        let has_background_glyph = BACKGROUND_CHAR_INDEX < charset_width * charset_height;
+
+        for y in 0..char_height * scale {
+            for x in 0..char_width * scale {
+                let (image_x, image_y) = ((origin_x + x) as u32, (origin_y + y) as u32);
+
+                if image_x >= image.width() || image_y >= image.height() {
+                    continue;
+                }
+
+                let (pixel_x, pixel_y) = self.source_pixel_pos(bunny_char, x / scale, y / scale);
+                let glyph = tint(
+                    self.texture().get_pixel_at(pixel_x, pixel_y),
+                    bunny_char.foreground,
+                );
+
+                let under = match bunny_char.background {
+                    Some(background) if has_background_glyph => {
+                        let (background_x, background_y) =
+                            self.source_pixel_pos(&background_char, x / scale, y / scale);
+                        let fill = tint(
+                            self.texture().get_pixel_at(background_x, background_y),
+                            background,
+                        );
+
+                        blend(*image.get_pixel(image_x, image_y), fill)
+                    }
+                    Some(background) => blend(*image.get_pixel(image_x, image_y), background),
+                    None => *image.get_pixel(image_x, image_y),
+                };
+
+                image.put_pixel(image_x, image_y, blend(under, glyph));
+            }
+        }
+    }
+
+    //Renders a whole grid to an image at an integer scale, empty cells are left transparent
+    pub fn render_grid(&self, grid: &ImageBunnyGrid, scale: u32) -> RgbaImage {
+        let (char_width, char_height) = self.char_dimensions();
+        let (width, height) = grid.dimensions();
+
+        let mut image = RgbaImage::new(
+            (width * char_width) as u32 * scale,
+            (height * char_height) as u32 * scale,
+        );
+
+        for ((x, y), cell) in grid.cells() {
+            if let Some(bunny_char) = cell {
+                self.render_char(&mut image, bunny_char, (x, y), scale);
+            }
+        }
+
+        image
+    }
+
+    //Renders the world cells visible through a viewport at their screen positions, the image covers the screen grid up to the viewport
+    pub fn render_viewport(
+        &self,
+        world: &ImageBunnyGrid,
+        viewport: &Viewport,
+        scale: u32,
+    ) -> RgbaImage {
+        let (char_width, char_height) = self.char_dimensions();
+
+        let mut image = RgbaImage::new(
+            (viewport.screen.right() * char_width) as u32 * scale,
+            (viewport.screen.bottom() * char_height) as u32 * scale,
+        );
+
+        for ((world_x, world_y), screen) in viewport.visible_cells() {
+            if let Some(bunny_char) = world.get(world_x, world_y) {
+                self.render_char(&mut image, bunny_char, screen, scale);
+            }
+        }
+
+        image
+    }
+
+    //Renders every glyph of the font in the same layout as the atlas, for showing what a font looks like
+    pub fn render_specimen(
+        &self,
+        foreground: Rgba<u8>,
+        background: Option<Rgba<u8>>,
+        scale: u32,
+    ) -> RgbaImage {
+        let (width, height) = self.charset_dimensions();
+        let mut grid = BunnyGrid::new((width, height));
+
+        for y in 0..height {
+            for x in 0..width {
+                grid.set(
+                    x,
+                    y,
+                    Some(BunnyChar::new(
+                        self.get_index_from_char_pos(x, y),
+                        foreground,
+                        background,
+                        CharRotation::None,
+                        CharMirror::None,
+                    )),
+                );
+            }
+        }
+
+        self.render_grid(&grid, scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rect::GridRect;
+
+    #[test]
+    fn base64_pads_partial_groups() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foob"), "Zm9vYg==");
+        assert_eq!(base64(&[0xfb, 0xff]), "+/8=");
+    }
+
+    #[test]
+    fn png_data_urls() {
+        let url = png_data_url(&RgbaImage::new(1, 1)).unwrap();
+
+        assert!(url.starts_with("data:image/png;base64,iVBORw0KGgo"));
+    }
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+    const CLEAR: Rgba<u8> = Rgba([0; 4]);
+
+    //Two 2x2 chars, the second inked at only its top left pixel
+    fn font() -> ImageBunnyFont {
+        let mut texture = RgbaImage::new(4, 2);
+        texture.put_pixel(2, 0, Rgba([255; 4]));
+
+        ImageBunnyFont::new(texture, (2, 2))
+    }
+
+    fn inked(rotation: CharRotation) -> ImageBunnyChar {
+        BunnyChar::new(1, RED, Some(BLUE), rotation, CharMirror::None)
+    }
+
+    #[test]
+    fn blending_and_tinting() {
+        assert_eq!(blend(BLUE, RED), RED);
+        assert_eq!(blend(BLUE, CLEAR), BLUE);
+        assert_eq!(blend(CLEAR, CLEAR), CLEAR);
+        assert_eq!(
+            blend(BLUE, Rgba([255, 0, 0, 128])),
+            Rgba([128, 0, 127, 255])
+        );
+
+        assert_eq!(tint(&Rgba([255; 4]), RED), RED);
+        assert_eq!(
+            tint(&Rgba([128, 128, 128, 255]), RED),
+            Rgba([128, 0, 0, 255])
+        );
+        assert_eq!(tint(&CLEAR, RED), CLEAR);
+    }
+
+    #[test]
+    fn grids_render_glyphs_over_backgrounds() {
+        let mut grid = BunnyGrid::new((2, 1));
+        grid.set(0, 0, Some(inked(CharRotation::None)));
+
+        let image = font().render_grid(&grid, 2);
+
+        assert_eq!(image.dimensions(), (8, 4));
+        assert_eq!(image.get_pixel(0, 0), &RED);
+        assert_eq!(image.get_pixel(1, 1), &RED);
+        assert_eq!(image.get_pixel(2, 0), &BLUE);
+        assert_eq!(image.get_pixel(0, 2), &BLUE);
+        assert_eq!(image.get_pixel(4, 0), &CLEAR);
+    }
+
+    #[test]
+    fn glyphs_are_rotated_like_the_ggez_renderer() {
+        let mut grid = BunnyGrid::new((1, 1));
+        grid.set(0, 0, Some(inked(CharRotation::Rotation90)));
+
+        let image = font().render_grid(&grid, 1);
+
+        assert_eq!(image.get_pixel(1, 0), &RED);
+        assert_eq!(image.get_pixel(0, 0), &BLUE);
+    }
+
+    #[test]
+    fn backgrounds_are_drawn_with_the_background_glyph() {
+        //Chars two pixels wide, with a background glyph that only covers the left pixel
+        let mut texture = RgbaImage::new(64, 23);
+        texture.put_pixel(
+            (BACKGROUND_CHAR_INDEX % 32 * 2) as u32,
+            (BACKGROUND_CHAR_INDEX / 32) as u32,
+            Rgba([255; 4]),
+        );
+
+        let font = ImageBunnyFont::new(texture, (2, 1));
+
+        let render = |mirror: CharMirror| {
+            let mut grid = BunnyGrid::new((1, 1));
+            grid.set(
+                0,
+                0,
+                Some(BunnyChar::new(
+                    0,
+                    RED,
+                    Some(BLUE),
+                    CharRotation::None,
+                    mirror,
+                )),
+            );
+
+            font.render_grid(&grid, 1)
+        };
+
+        let image = render(CharMirror::None);
+        assert_eq!(image.get_pixel(0, 0), &BLUE);
+        assert_eq!(image.get_pixel(1, 0), &CLEAR);
+
+        //The background glyph is oriented along with the char
+        let image = render(CharMirror::MirrorX);
+        assert_eq!(image.get_pixel(0, 0), &CLEAR);
+        assert_eq!(image.get_pixel(1, 0), &BLUE);
+    }
+
+    #[test]
+    fn viewports_render_at_their_screen_position() {
+        let mut world = BunnyGrid::new((3, 1));
+        world.set(2, 0, Some(inked(CharRotation::None)));
+
+        let mut viewport = Viewport::new(GridRect::new(1, 0, 1, 1), (3, 1)).clamp(false);
+        viewport.scroll_to(2, 0);
+
+        let image = font().render_viewport(&world, &viewport, 1);
+
+        assert_eq!(image.dimensions(), (4, 2));
+        assert_eq!(image.get_pixel(2, 0), &RED);
+        assert_eq!(image.get_pixel(3, 0), &BLUE);
+        assert_eq!(image.get_pixel(0, 0), &CLEAR);
+    }
+
+    #[test]
+    fn specimens_show_every_glyph() {
+        let image = font().render_specimen(RED, Some(BLUE), 1);
+
+        assert_eq!(image.dimensions(), (4, 2));
+        assert_eq!(image.get_pixel(2, 0), &RED);
+        assert_eq!(image.get_pixel(3, 0), &BLUE);
+        assert_eq!(image.get_pixel(0, 0), &BLUE);
+        assert_eq!(image.get_pixel(1, 1), &BLUE);
+    }
+}
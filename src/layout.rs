@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::rect::GridRect;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Direction {
+    Horizontal,
+    Vertical,
+}
+
+//How much of the split axis a region takes up, in cells
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Constraint {
+    //Exactly this many cells
+    Fixed(usize),
+    //A percentage of the space being split
+    Percentage(usize),
+    //At least this many cells, growing like Fill(1) when there is space left over
+    Min(usize),
+    //Grows like Fill(1) but never past this many cells
+    Max(usize),
+    //A weighted share of the space left over once everything else is placed
+    Fill(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Layout {
+    pub direction: Direction,
+    pub constraints: Vec<Constraint>,
+    pub spacing: usize,
+}
+
+impl Layout {
+    pub fn new(direction: Direction, constraints: Vec<Constraint>) -> Self {
+        Self {
+            direction,
+            constraints,
+            spacing: 0,
+        }
+    }
+
+    //Splits into side by side columns
+    pub fn horizontal(constraints: Vec<Constraint>) -> Self {
+        Self::new(Direction::Horizontal, constraints)
+    }
+
+    //Splits into stacked rows
+    pub fn vertical(constraints: Vec<Constraint>) -> Self {
+        Self::new(Direction::Vertical, constraints)
+    }
+
+    //The number of cells left empty between neighbouring regions
+    pub fn spacing(self, spacing: usize) -> Self {
+        Self { spacing, ..self }
+    }
+
+    //Splits a rect into one region per constraint, regions that don't fit end up empty
+    pub fn split(&self, area: GridRect) -> Vec<GridRect> {
+        let length = match self.direction {
+            Direction::Horizontal => area.width,
+            Direction::Vertical => area.height,
+        };
+
+        let gaps = self.spacing * self.constraints.len().saturating_sub(1);
+        let sizes = solve(&self.constraints, length.saturating_sub(gaps));
+
+        let mut position = 0;
+
+        sizes
+            .into_iter()
+            .map(|size| {
+                let start = position.min(length);
+                let size = size.min(length - start);
+                position = start + size + self.spacing;
+
+                match self.direction {
+                    Direction::Horizontal => {
+                        GridRect::new(area.x + start, area.y, size, area.height)
+                    }
+                    Direction::Vertical => GridRect::new(area.x, area.y + start, area.width, size),
+                }
+            })
+            .collect()
+    }
+}
+
+fn solve(constraints: &[Constraint], total: usize) -> Vec<usize> {
+    let mut sizes: Vec<usize> = constraints
+        .iter()
+        .map(|constraint| match constraint {
+            Constraint::Fixed(size) | Constraint::Min(size) => *size,
+            Constraint::Percentage(percentage) => total * percentage / 100,
+            Constraint::Max(_) | Constraint::Fill(_) => 0,
+        })
+        .collect();
+
+    //Space is handed out first come first served when the fixed sizes don't fit
+    let mut remaining = total;
+    for size in sizes.iter_mut() {
+        *size = (*size).min(remaining);
+        remaining -= *size;
+    }
+
+    let weight = |constraint: &Constraint| match constraint {
+        Constraint::Min(_) | Constraint::Max(_) => 1,
+        Constraint::Fill(weight) => *weight,
+        Constraint::Fixed(_) | Constraint::Percentage(_) => 0,
+    };
+
+    let mut growing: Vec<usize> = (0..constraints.len())
+        .filter(|index| weight(&constraints[*index]) > 0)
+        .collect();
+
+    //Capped regions that would grow past their maximum are fixed at it and the rest is shared out again
+    while remaining > 0 && !growing.is_empty() {
+        let total_weight: usize = growing
+            .iter()
+            .map(|index| weight(&constraints[*index]))
+            .sum();
+        let shares: Vec<usize> = growing
+            .iter()
+            .map(|index| remaining * weight(&constraints[*index]) / total_weight)
+            .collect();
+
+        let capped: Vec<usize> = growing
+            .iter()
+            .zip(shares.iter())
+            .filter_map(|(index, share)| match constraints[*index] {
+                Constraint::Max(max) if sizes[*index] + share >= max => Some(*index),
+                _ => None,
+            })
+            .collect();
+
+        if capped.is_empty() {
+            let mut leftover = remaining - shares.iter().sum::<usize>();
+
+            for (index, share) in growing.iter().zip(shares) {
+                let extra = if leftover > 0 { 1 } else { 0 };
+                leftover -= extra;
+                sizes[*index] += share + extra;
+            }
+
+            break;
+        }
+
+        for index in capped {
+            if let Constraint::Max(max) = constraints[index] {
+                let extra = max.saturating_sub(sizes[index]).min(remaining);
+                sizes[index] += extra;
+                remaining -= extra;
+            }
+
+            growing.retain(|growing_index| *growing_index != index);
+        }
+    }
+
+    sizes
+}
+
+//A tree of nested splits whose leaves are named panes
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LayoutNode {
+    Pane(String),
+    Split(Layout, Vec<LayoutNode>),
+}
+
+impl LayoutNode {
+    pub fn pane(name: &str) -> Self {
+        LayoutNode::Pane(name.to_owned())
+    }
+
+    pub fn split(layout: Layout, children: Vec<LayoutNode>) -> Self {
+        LayoutNode::Split(layout, children)
+    }
+
+    //Works out the area of every pane, should be called again whenever the grid is resized
+    pub fn resolve(&self, area: GridRect) -> HashMap<String, GridRect> {
+        let mut panes = HashMap::new();
+        self.resolve_into(area, &mut panes);
+        panes
+    }
+
+    fn resolve_into(&self, area: GridRect, panes: &mut HashMap<String, GridRect>) {
+        match self {
+            LayoutNode::Pane(name) => {
+                panes.insert(name.clone(), area);
+            }
+            LayoutNode::Split(layout, children) => {
+                for (child, child_area) in children.iter().zip(layout.split(area)) {
+                    child.resolve_into(child_area, panes);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Constraint::*;
+
+    #[test]
+    fn fixed_and_percentage_sizes_come_first() {
+        assert_eq!(solve(&[Fixed(10), Fill(1)], 30), vec![10, 20]);
+        assert_eq!(solve(&[Percentage(25), Fill(1)], 20), vec![5, 15]);
+    }
+
+    #[test]
+    fn fixed_sizes_that_dont_fit_are_first_come_first_served() {
+        assert_eq!(solve(&[Fixed(8), Fixed(8), Fill(1)], 10), vec![8, 2, 0]);
+    }
+
+    #[test]
+    fn fills_share_by_weight_and_hand_out_the_remainder() {
+        assert_eq!(solve(&[Fill(1), Fill(1), Fill(1)], 10), vec![4, 3, 3]);
+        assert_eq!(solve(&[Fill(1), Fill(3)], 20), vec![5, 15]);
+        assert_eq!(solve(&[Fill(0), Fill(1)], 20), vec![0, 20]);
+    }
+
+    #[test]
+    fn min_grows_from_its_minimum() {
+        assert_eq!(solve(&[Min(4), Fill(1)], 10), vec![7, 3]);
+        assert_eq!(solve(&[Min(12), Fill(1)], 10), vec![10, 0]);
+    }
+
+    #[test]
+    fn max_caps_growth_and_passes_the_rest_on() {
+        assert_eq!(solve(&[Max(5), Fill(1)], 20), vec![5, 15]);
+        assert_eq!(solve(&[Max(3), Max(100), Fill(2)], 40), vec![3, 13, 24]);
+        assert_eq!(solve(&[Max(50), Fill(1)], 20), vec![10, 10]);
+    }
+
+    #[test]
+    fn space_is_left_over_when_everything_is_capped() {
+        assert_eq!(solve(&[Max(2), Max(3)], 10), vec![2, 3]);
+        assert_eq!(solve(&[Max(2), Fixed(1)], 10), vec![2, 1]);
+    }
+
+    #[test]
+    fn split_leaves_gaps_between_regions() {
+        let layout = Layout::horizontal(vec![Fixed(3), Fill(1)]).spacing(1);
+
+        assert_eq!(
+            layout.split(GridRect::new(2, 1, 10, 5)),
+            vec![GridRect::new(2, 1, 3, 5), GridRect::new(6, 1, 6, 5)]
+        );
+    }
+
+    #[test]
+    fn split_gives_regions_that_dont_fit_no_space() {
+        let layout = Layout::vertical(vec![Fixed(4), Fixed(4), Fixed(4)]).spacing(1);
+
+        assert_eq!(
+            layout.split(GridRect::new(0, 0, 3, 6)),
+            vec![
+                GridRect::new(0, 0, 3, 4),
+                GridRect::new(0, 5, 3, 0),
+                GridRect::new(0, 6, 3, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_panes_resolve_to_their_areas() {
+        let root = LayoutNode::split(
+            Layout::vertical(vec![Fill(1), Fixed(3)]),
+            vec![
+                LayoutNode::split(
+                    Layout::horizontal(vec![Fill(1), Max(10)]),
+                    vec![LayoutNode::pane("map"), LayoutNode::pane("sidebar")],
+                ),
+                LayoutNode::pane("log"),
+            ],
+        );
+
+        let panes = root.resolve(GridRect::new(0, 0, 40, 20));
+
+        assert_eq!(panes["map"], GridRect::new(0, 0, 30, 17));
+        assert_eq!(panes["sidebar"], GridRect::new(30, 0, 10, 17));
+        assert_eq!(panes["log"], GridRect::new(0, 17, 40, 3));
+    }
+}
@@ -1,5 +1,21 @@
+pub mod border;
+pub mod canvas;
 pub mod char;
 pub mod char_transforms;
+pub mod charmap;
 pub mod font;
+pub mod formats;
+pub mod grid;
 pub mod integrations;
+pub mod layout;
+pub mod markup;
+pub mod message_log;
+pub mod prefab;
+pub mod rect;
+pub mod region;
+pub mod shapes;
+pub mod terminal;
+pub mod text;
 pub mod traits;
+pub mod ui;
+pub mod viewport;
@@ -0,0 +1,535 @@
+use std::{collections::HashMap, error::Error, fmt};
+
+use crate::{
+    char::BunnyChar,
+    char_transforms::{CharMirror, CharRotation},
+    charmap::CharMap,
+    grid::BunnyGrid,
+    rect::GridRect,
+    text::{TextCell, TextOptions},
+    traits::color::Color,
+};
+
+//Markup looks like "You hit the [red]orc[/] for [fg=#ffff00 bg=black]5[/] [@heart]"
+//
+//  [name] or [#rrggbb]      sets the foreground colour
+//  [fg=...] [bg=...]        sets the foreground or background colour, bg=none removes the background
+//  [rot=90] [mirror=x]      sets the rotation (0, 90, 180, 270) or mirroring (none, x, y, both)
+//  [/]                      closes the most recently opened tag
+//  [@name]                  inserts a single glyph looked up by name in the charmap
+//  \[ \] \\                 escape a literal bracket or backslash
+
+//The char that glyph overrides are laid out as, it isn't whitespace so glyphs wrap like letters
+pub const GLYPH_PLACEHOLDER: char = '\u{fffc}';
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpanStyle<C> {
+    pub foreground: Option<C>,
+    pub background: Option<Option<C>>,
+    pub rotation: Option<CharRotation>,
+    pub mirror: Option<CharMirror>,
+}
+
+impl<C> Default for SpanStyle<C> {
+    fn default() -> Self {
+        Self {
+            foreground: None,
+            background: None,
+            rotation: None,
+            mirror: None,
+        }
+    }
+}
+
+impl<C> SpanStyle<C>
+where
+    C: Color,
+{
+    //Overrides the parts of a template char that this style sets
+    pub fn apply(&self, template: BunnyChar<C>) -> BunnyChar<C> {
+        BunnyChar {
+            foreground: self.foreground.unwrap_or(template.foreground),
+            background: self.background.unwrap_or(template.background),
+            rotation: self.rotation.unwrap_or(template.rotation),
+            mirror: self.mirror.unwrap_or(template.mirror),
+            ..template
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpanContent {
+    Text(String),
+    Glyph(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span<C> {
+    pub content: SpanContent,
+    pub style: SpanStyle<C>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MarkupErrorKind {
+    UnclosedTag,
+    EmptyTag,
+    UnmatchedClose,
+    TrailingEscape,
+    InvalidEscape(char),
+    UnknownColor(String),
+    UnknownGlyph(String),
+    UnknownAttribute(String),
+    InvalidValue(String, String),
+}
+
+//An error in some markup, the position is the index of the offending char in the source text
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarkupError {
+    pub position: usize,
+    pub kind: MarkupErrorKind,
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            MarkupErrorKind::UnclosedTag => write!(f, "unclosed tag"),
+            MarkupErrorKind::EmptyTag => write!(f, "empty tag"),
+            MarkupErrorKind::UnmatchedClose => write!(f, "closing tag without an open tag"),
+            MarkupErrorKind::TrailingEscape => write!(f, "escape at end of text"),
+            MarkupErrorKind::InvalidEscape(ch) => write!(f, "invalid escape '\\{}'", ch),
+            MarkupErrorKind::UnknownColor(name) => write!(f, "unknown color '{}'", name),
+            MarkupErrorKind::UnknownGlyph(name) => write!(f, "unknown glyph '{}'", name),
+            MarkupErrorKind::UnknownAttribute(name) => write!(f, "unknown attribute '{}'", name),
+            MarkupErrorKind::InvalidValue(attribute, value) => {
+                write!(f, "invalid value '{}' for attribute '{}'", value, attribute)
+            }
+        }?;
+
+        write!(f, " at position {}", self.position)
+    }
+}
+
+impl Error for MarkupError {}
+
+//Resolves the colour and glyph names used in markup
+#[derive(Clone, Debug)]
+pub struct MarkupPalette<C> {
+    colors: HashMap<String, C>,
+}
+
+impl<C> Default for MarkupPalette<C> {
+    fn default() -> Self {
+        Self {
+            colors: HashMap::new(),
+        }
+    }
+}
+
+impl<C> MarkupPalette<C>
+where
+    C: Color,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //A palette with the 16 basic terminal colour names
+    pub fn basic() -> Self {
+        [
+            ("black", [0x00, 0x00, 0x00]),
+            ("red", [0xaa, 0x00, 0x00]),
+            ("green", [0x00, 0xaa, 0x00]),
+            ("yellow", [0xaa, 0x55, 0x00]),
+            ("blue", [0x00, 0x00, 0xaa]),
+            ("magenta", [0xaa, 0x00, 0xaa]),
+            ("cyan", [0x00, 0xaa, 0xaa]),
+            ("white", [0xaa, 0xaa, 0xaa]),
+            ("grey", [0x55, 0x55, 0x55]),
+            ("light_red", [0xff, 0x55, 0x55]),
+            ("light_green", [0x55, 0xff, 0x55]),
+            ("light_yellow", [0xff, 0xff, 0x55]),
+            ("light_blue", [0x55, 0x55, 0xff]),
+            ("light_magenta", [0xff, 0x55, 0xff]),
+            ("light_cyan", [0x55, 0xff, 0xff]),
+            ("bright_white", [0xff, 0xff, 0xff]),
+        ]
+        .iter()
+        .fold(Self::new(), |palette, (name, [r, g, b])| {
+            palette.with_color(name, C::from_rgba8([*r, *g, *b, 0xff]))
+        })
+    }
+
+    pub fn insert_color(&mut self, name: &str, color: C) {
+        self.colors.insert(name.to_owned(), color);
+    }
+
+    pub fn with_color(mut self, name: &str, color: C) -> Self {
+        self.insert_color(name, color);
+        self
+    }
+
+    //Looks up a named colour or parses a #rgb, #rrggbb or #rrggbbaa hex colour
+    pub fn color(&self, name: &str) -> Option<C> {
+        if let Some(hex) = name.strip_prefix('#') {
+            return parse_hex_color(hex).map(C::from_rgba8);
+        }
+
+        self.colors.get(name).copied()
+    }
+}
+
+fn parse_hex_color(hex: &str) -> Option<[u8; 4]> {
+    if !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let channel = |range: std::ops::Range<usize>| u8::from_str_radix(&hex[range], 16).ok();
+
+    match hex.len() {
+        3 => {
+            let short = |index: usize| channel(index..index + 1).map(|value| value * 0x11);
+            Some([short(0)?, short(1)?, short(2)?, 0xff])
+        }
+        6 => Some([channel(0..2)?, channel(2..4)?, channel(4..6)?, 0xff]),
+        8 => Some([channel(0..2)?, channel(2..4)?, channel(4..6)?, channel(6..8)?]),
+        _ => None,
+    }
+}
+
+//Parses markup into styled spans, glyph names are looked up in the charmap
+pub fn parse_markup<C>(
+    markup: &str,
+    palette: &MarkupPalette<C>,
+    charmap: &CharMap,
+) -> Result<Vec<Span<C>>, MarkupError>
+where
+    C: Color,
+{
+    let chars: Vec<char> = markup.chars().collect();
+
+    let mut spans = Vec::new();
+    let mut styles = vec![SpanStyle::default()];
+    let mut text = String::new();
+
+    let flush = |text: &mut String, spans: &mut Vec<Span<C>>, style: SpanStyle<C>| {
+        if !text.is_empty() {
+            spans.push(Span {
+                content: SpanContent::Text(std::mem::take(text)),
+                style,
+            });
+        }
+    };
+
+    let mut position = 0;
+
+    while position < chars.len() {
+        let style = *styles.last().unwrap();
+
+        match chars[position] {
+            '\\' => {
+                match chars.get(position + 1) {
+                    Some(escaped @ ('[' | ']' | '\\')) => text.push(*escaped),
+                    Some(other) => {
+                        return Err(MarkupError {
+                            position,
+                            kind: MarkupErrorKind::InvalidEscape(*other),
+                        })
+                    }
+                    None => {
+                        return Err(MarkupError {
+                            position,
+                            kind: MarkupErrorKind::TrailingEscape,
+                        })
+                    }
+                }
+
+                position += 2;
+            }
+
+            '[' => {
+                let end = chars[position..]
+                    .iter()
+                    .position(|ch| *ch == ']')
+                    .map(|offset| position + offset)
+                    .ok_or(MarkupError {
+                        position,
+                        kind: MarkupErrorKind::UnclosedTag,
+                    })?;
+
+                let tag: String = chars[position + 1..end].iter().collect();
+                let tag_position = position + 1;
+
+                flush(&mut text, &mut spans, style);
+
+                if tag.trim().is_empty() {
+                    return Err(MarkupError {
+                        position,
+                        kind: MarkupErrorKind::EmptyTag,
+                    });
+                } else if tag.trim() == "/" {
+                    if styles.len() == 1 {
+                        return Err(MarkupError {
+                            position,
+                            kind: MarkupErrorKind::UnmatchedClose,
+                        });
+                    }
+
+                    styles.pop();
+                } else if let Some(name) = tag.strip_prefix('@') {
+                    let index = charmap.glyph(name.trim()).ok_or(MarkupError {
+                        position: tag_position + 1,
+                        kind: MarkupErrorKind::UnknownGlyph(name.trim().to_owned()),
+                    })?;
+
+                    spans.push(Span {
+                        content: SpanContent::Glyph(index),
+                        style,
+                    });
+                } else {
+                    styles.push(parse_tag(&tag, tag_position, style, palette)?);
+                }
+
+                position = end + 1;
+            }
+
+            ch => {
+                text.push(ch);
+                position += 1;
+            }
+        }
+    }
+
+    flush(&mut text, &mut spans, *styles.last().unwrap());
+
+    Ok(spans)
+}
+
+//Applies every whitespace separated attribute of a tag on top of the enclosing style
+fn parse_tag<C>(
+    tag: &str,
+    tag_position: usize,
+    mut style: SpanStyle<C>,
+    palette: &MarkupPalette<C>,
+) -> Result<SpanStyle<C>, MarkupError>
+where
+    C: Color,
+{
+    let mut offset = 0;
+
+    for token in tag.split(' ') {
+        let position = tag_position + offset;
+        offset += token.chars().count() + 1;
+
+        if token.is_empty() {
+            continue;
+        }
+
+        let error = |kind| MarkupError { position, kind };
+
+        let color = |name: &str| {
+            palette
+                .color(name)
+                .ok_or_else(|| error(MarkupErrorKind::UnknownColor(name.to_owned())))
+        };
+
+        let invalid_value = |attribute: &str, value: &str| {
+            error(MarkupErrorKind::InvalidValue(
+                attribute.to_owned(),
+                value.to_owned(),
+            ))
+        };
+
+        match token.split_once('=') {
+            None => style.foreground = Some(color(token)?),
+            Some(("fg", value)) => style.foreground = Some(color(value)?),
+            Some(("bg", "none")) => style.background = Some(None),
+            Some(("bg", value)) => style.background = Some(Some(color(value)?)),
+            Some(("rot", value)) => {
+                style.rotation = Some(match value {
+                    "0" => CharRotation::None,
+                    "90" => CharRotation::Rotation90,
+                    "180" => CharRotation::Rotation180,
+                    "270" => CharRotation::Rotation270,
+                    _ => return Err(invalid_value("rot", value)),
+                })
+            }
+            Some(("mirror", value)) => {
+                style.mirror = Some(match value {
+                    "none" => CharMirror::None,
+                    "x" => CharMirror::MirrorX,
+                    "y" => CharMirror::MirrorY,
+                    "both" => CharMirror::MirrorBoth,
+                    _ => return Err(invalid_value("mirror", value)),
+                })
+            }
+            Some((attribute, _)) => {
+                return Err(error(MarkupErrorKind::UnknownAttribute(
+                    attribute.to_owned(),
+                )))
+            }
+        }
+    }
+
+    Ok(style)
+}
+
+impl<C> TextCell<C>
+where
+    C: Color,
+{
+    //Converts styled spans into text cells, unstyled parts of the spans are taken from the template
+    pub fn from_spans(spans: &[Span<C>], template: BunnyChar<C>, charmap: &CharMap) -> Vec<Self> {
+        spans
+            .iter()
+            .flat_map(|span| {
+                let template = span.style.apply(template);
+
+                match &span.content {
+                    SpanContent::Text(text) => Self::from_str(text, template, charmap),
+                    SpanContent::Glyph(index) => {
+                        vec![Self::new(GLYPH_PLACEHOLDER, template.index(*index))]
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+impl<C> BunnyGrid<C>
+where
+    C: Color,
+{
+    //Parses and prints markup, optionally word wrapped to the width of the area
+    #[allow(clippy::too_many_arguments)]
+    pub fn print_markup(
+        &mut self,
+        charmap: &CharMap,
+        palette: &MarkupPalette<C>,
+        area: GridRect,
+        markup: &str,
+        template: BunnyChar<C>,
+        wrap: bool,
+        options: &TextOptions,
+    ) -> Result<GridRect, MarkupError> {
+        let spans = parse_markup(markup, palette, charmap)?;
+        let cells = TextCell::from_spans(&spans, template, charmap);
+
+        Ok(self.print_cells(charmap, area, &cells, wrap, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::color::TestColor;
+
+    fn parse(markup: &str) -> Result<Vec<Span<TestColor>>, MarkupError> {
+        parse_markup(markup, &MarkupPalette::basic(), &CharMap::cp437())
+    }
+
+    fn error_at(markup: &str) -> (usize, MarkupErrorKind) {
+        let error = parse(markup).unwrap_err();
+        (error.position, error.kind)
+    }
+
+    fn text(text: &str, style: SpanStyle<TestColor>) -> Span<TestColor> {
+        Span {
+            content: SpanContent::Text(text.to_owned()),
+            style,
+        }
+    }
+
+    #[test]
+    fn nested_tags_inherit_and_close_in_order() {
+        let red = TestColor([0xaa, 0, 0, 0xff]);
+        let yellow = TestColor([0xff, 0xff, 0, 0xff]);
+
+        let red_style = SpanStyle {
+            foreground: Some(red),
+            ..SpanStyle::default()
+        };
+        let nested_style = SpanStyle {
+            background: Some(Some(yellow)),
+            rotation: Some(CharRotation::Rotation90),
+            ..red_style
+        };
+
+        assert_eq!(
+            parse("a[red]b[bg=#ff0 rot=90]c[/]d[/]e").unwrap(),
+            vec![
+                text("a", SpanStyle::default()),
+                text("b", red_style),
+                text("c", nested_style),
+                text("d", red_style),
+                text("e", SpanStyle::default()),
+            ]
+        );
+    }
+
+    #[test]
+    fn glyphs_and_escapes() {
+        assert_eq!(
+            parse(r"\[x\] [@☺]").unwrap(),
+            vec![
+                text("[x] ", SpanStyle::default()),
+                Span {
+                    content: SpanContent::Glyph(1),
+                    style: SpanStyle::default(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn hex_colors() {
+        assert_eq!(parse_hex_color("f80"), Some([0xff, 0x88, 0x00, 0xff]));
+        assert_eq!(parse_hex_color("102030"), Some([0x10, 0x20, 0x30, 0xff]));
+        assert_eq!(parse_hex_color("10203040"), Some([0x10, 0x20, 0x30, 0x40]));
+        assert_eq!(parse_hex_color("12345"), None);
+        assert_eq!(parse_hex_color("+12"), None);
+    }
+
+    #[test]
+    fn error_positions_point_at_the_offending_char() {
+        assert_eq!(error_at("ab[red"), (2, MarkupErrorKind::UnclosedTag));
+        assert_eq!(error_at("ab[ ]"), (2, MarkupErrorKind::EmptyTag));
+        assert_eq!(error_at("a[/]"), (1, MarkupErrorKind::UnmatchedClose));
+        assert_eq!(error_at("abc\\"), (3, MarkupErrorKind::TrailingEscape));
+        assert_eq!(error_at("a\\n"), (1, MarkupErrorKind::InvalidEscape('n')));
+    }
+
+    #[test]
+    fn error_positions_inside_tags_point_at_the_attribute() {
+        assert_eq!(
+            error_at("x[red  teal]"),
+            (7, MarkupErrorKind::UnknownColor("teal".to_owned()))
+        );
+        assert_eq!(
+            error_at("[fg=red rot=45]"),
+            (
+                8,
+                MarkupErrorKind::InvalidValue("rot".to_owned(), "45".to_owned())
+            )
+        );
+        assert_eq!(
+            error_at("[size=2]"),
+            (1, MarkupErrorKind::UnknownAttribute("size".to_owned()))
+        );
+        assert_eq!(
+            error_at("ab[@nope]"),
+            (4, MarkupErrorKind::UnknownGlyph("nope".to_owned()))
+        );
+    }
+
+    #[test]
+    fn positions_count_chars_not_bytes() {
+        assert_eq!(error_at("é☺[/]"), (2, MarkupErrorKind::UnmatchedClose));
+    }
+
+    #[test]
+    fn errors_display_their_position() {
+        let error = parse("[bogus]").unwrap_err();
+
+        assert_eq!(error.to_string(), "unknown color 'bogus' at position 1");
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::VecDeque;
+
+use crate::{
+    char::BunnyChar,
+    charmap::CharMap,
+    grid::BunnyGrid,
+    markup::{parse_markup, MarkupError, MarkupPalette},
+    rect::GridRect,
+    text::{layout_lines, TextCell},
+    traits::color::Color,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+struct LogMessage<C> {
+    cells: Vec<TextCell<C>>,
+    count: usize,
+    //The style of the repeat count, None for empty messages pushed without a template
+    template: Option<BunnyChar<C>>,
+}
+
+//A console style log of styled messages that wraps to the width it is drawn at
+#[derive(Clone, Debug)]
+pub struct MessageLog<C> {
+    messages: VecDeque<LogMessage<C>>,
+    capacity: usize,
+    scroll: usize,
+    wrapped: Option<(usize, Vec<Vec<TextCell<C>>>)>,
+}
+
+impl<C> MessageLog<C>
+where
+    C: Color,
+{
+    //Creates a log that forgets its oldest messages once it holds more than the given number
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            messages: VecDeque::new(),
+            capacity,
+            scroll: 0,
+            wrapped: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.messages.clear();
+        self.scroll = 0;
+        self.wrapped = None;
+    }
+
+    //Adds a message, a message identical to the previous one just bumps its repeat count
+    //An empty message takes up a blank line
+    pub fn push(&mut self, cells: Vec<TextCell<C>>) {
+        self.push_message(cells, None);
+    }
+
+    pub fn push_str(&mut self, text: &str, template: BunnyChar<C>, charmap: &CharMap) {
+        self.push_message(TextCell::from_str(text, template, charmap), Some(template));
+    }
+
+    pub fn push_markup(
+        &mut self,
+        markup: &str,
+        template: BunnyChar<C>,
+        palette: &MarkupPalette<C>,
+        charmap: &CharMap,
+    ) -> Result<(), MarkupError> {
+        let spans = parse_markup(markup, palette, charmap)?;
+        self.push_message(
+            TextCell::from_spans(&spans, template, charmap),
+            Some(template),
+        );
+
+        Ok(())
+    }
+
+    //The repeat count is styled like the message's last cell, or its template when it is empty
+    //Empty messages without a template have no style for a count, so each of them is kept as its own line
+    fn push_message(&mut self, cells: Vec<TextCell<C>>, template: Option<BunnyChar<C>>) {
+        match self.messages.back_mut() {
+            Some(last) if last.cells == cells && last.template.is_some() => last.count += 1,
+            _ => {
+                let template = cells.last().map(|cell| cell.bunny_char).or(template);
+                self.messages.push_back(LogMessage {
+                    cells,
+                    count: 1,
+                    template,
+                });
+
+                while self.messages.len() > self.capacity {
+                    self.messages.pop_front();
+                }
+            }
+        }
+
+        self.wrapped = None;
+    }
+
+    //How many wrapped lines the view is scrolled up from the newest message
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll += lines;
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll = 0;
+    }
+
+    //Wraps every message to the given width, reusing the previous wrapping if nothing changed
+    fn wrapped_lines(&mut self, width: usize, charmap: &CharMap) -> &[Vec<TextCell<C>>] {
+        let stale = match &self.wrapped {
+            Some((wrapped_width, _)) => *wrapped_width != width,
+            None => true,
+        };
+
+        if stale {
+            let lines = self
+                .messages
+                .iter()
+                .flat_map(|message| {
+                    let mut cells = message.cells.clone();
+
+                    if let Some(template) = message.template.filter(|_| message.count > 1) {
+                        let separator = if cells.is_empty() { "" } else { " " };
+                        let suffix = format!("{}x{}", separator, message.count);
+                        cells.extend(TextCell::from_str(&suffix, template, charmap));
+                    }
+
+                    //Laying out no cells gives no lines, but an empty message is still a blank line
+                    if cells.is_empty() {
+                        vec![Vec::new()]
+                    } else {
+                        layout_lines(&cells, width, true, 4)
+                    }
+                })
+                .collect();
+
+            self.wrapped = Some((width, lines));
+        }
+
+        &self.wrapped.as_ref().unwrap().1
+    }
+
+    //Draws the newest messages that fit at the bottom of an area, with an optional scrollbar of (track, thumb) chars down its right side
+    pub fn draw(
+        &mut self,
+        grid: &mut BunnyGrid<C>,
+        charmap: &CharMap,
+        area: GridRect,
+        scrollbar: Option<(BunnyChar<C>, BunnyChar<C>)>,
+    ) {
+        let area = area.intersection(&GridRect::from_dimensions(grid.dimensions()));
+
+        if area.is_empty() {
+            return;
+        }
+
+        let width = if scrollbar.is_some() {
+            area.width - 1
+        } else {
+            area.width
+        };
+        let visible = area.height;
+
+        let total = self.wrapped_lines(width, charmap).len();
+        self.scroll = self.scroll.min(total.saturating_sub(visible));
+        let first = total.saturating_sub(visible + self.scroll);
+
+        for (x, y) in area.cells() {
+            grid.set(x, y, None);
+        }
+
+        let lines = &self.wrapped.as_ref().unwrap().1;
+
+        for (row, line) in lines.iter().skip(first).take(visible).enumerate() {
+            for (column, cell) in line.iter().enumerate() {
+                grid.set(area.x + column, area.y + row, Some(cell.bunny_char));
+            }
+        }
+
+        if let Some((track, thumb)) = scrollbar {
+            let track_area = GridRect::new(area.right() - 1, area.y, 1, area.height);
+            grid.draw_scrollbar(track_area, first, visible, total, track, thumb);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        char_transforms::{CharMirror, CharRotation},
+        traits::color::TestColor,
+    };
+
+    fn template() -> BunnyChar<TestColor> {
+        BunnyChar::new(
+            0,
+            TestColor([255; 4]),
+            None,
+            CharRotation::None,
+            CharMirror::None,
+        )
+    }
+
+    fn log(messages: &[&str], capacity: usize) -> MessageLog<TestColor> {
+        let mut log = MessageLog::new(capacity);
+
+        for message in messages {
+            log.push_str(message, template(), &CharMap::cp437());
+        }
+
+        log
+    }
+
+    fn rows(log: &mut MessageLog<TestColor>, width: usize, height: usize) -> Vec<String> {
+        let charmap = CharMap::cp437();
+        let mut grid = BunnyGrid::new((width, height));
+
+        log.draw(
+            &mut grid,
+            &charmap,
+            GridRect::new(0, 0, width, height),
+            None,
+        );
+
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        grid.get(x, y)
+                            .and_then(|bunny_char| charmap.char_of(bunny_char.index))
+                            .unwrap_or('.')
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn repeated_messages_collapse_into_a_count() {
+        let mut log = log(&["hit", "hit", "hit", "miss"], 10);
+
+        assert_eq!(log.len(), 2);
+        assert_eq!(rows(&mut log, 8, 2), vec!["hit x3..", "miss...."]);
+    }
+
+    #[test]
+    fn oldest_messages_are_forgotten_past_capacity() {
+        let mut log = log(&["a", "b", "c"], 2);
+
+        assert_eq!(log.len(), 2);
+        assert_eq!(rows(&mut log, 2, 3), vec!["b.", "c.", ".."]);
+    }
+
+    #[test]
+    fn newest_lines_are_shown_and_scrolling_is_clamped() {
+        let mut log = log(&["one two", "three"], 10);
+
+        assert_eq!(rows(&mut log, 5, 2), vec!["two..", "three"]);
+
+        log.scroll_up(10);
+        assert_eq!(rows(&mut log, 5, 2), vec!["one..", "two.."]);
+        assert_eq!(log.scroll(), 1);
+
+        log.scroll_down(5);
+        assert_eq!(log.scroll(), 0);
+    }
+
+    #[test]
+    fn scrollbar_takes_the_last_column() {
+        let charmap = CharMap::cp437();
+        let mut log = log(&["a", "b", "c"], 10);
+        let mut grid = BunnyGrid::new((3, 2));
+        let track = template().index(1);
+        let thumb = template().index(2);
+
+        log.draw(
+            &mut grid,
+            &charmap,
+            GridRect::new(0, 0, 3, 2),
+            Some((track, thumb)),
+        );
+
+        assert_eq!(grid.get(2, 0).map(|bunny_char| bunny_char.index), Some(1));
+        assert_eq!(grid.get(2, 1).map(|bunny_char| bunny_char.index), Some(2));
+    }
+
+    #[test]
+    fn empty_messages_are_blank_lines() {
+        let mut log = log(&["a", "", "b", "", ""], 10);
+
+        assert_eq!(log.len(), 4);
+        assert_eq!(rows(&mut log, 3, 4), vec!["a..", "...", "b..", "x2."]);
+
+        let mut log = MessageLog::new(10);
+        log.push(Vec::new());
+        log.push(Vec::new());
+        log.push_str("c", template(), &CharMap::cp437());
+
+        assert_eq!(log.len(), 3);
+        assert_eq!(rows(&mut log, 3, 3), vec!["...", "...", "c.."]);
+    }
+
+    #[test]
+    fn markup_errors_add_nothing() {
+        let mut log = MessageLog::new(10);
+
+        let result = log.push_markup(
+            "[nope]x",
+            template(),
+            &MarkupPalette::basic(),
+            &CharMap::cp437(),
+        );
+
+        assert!(result.is_err());
+        assert!(log.is_empty());
+    }
+}
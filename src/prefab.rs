@@ -0,0 +1,556 @@
+use std::{collections::HashMap, error::Error, fmt};
+
+#[cfg(feature = "prefabs")]
+use std::{fs, io, path::Path};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    char::BunnyChar,
+    char_transforms::{CharMirror, CharRotation},
+    charmap::CharMap,
+    grid::BunnyGrid,
+    markup::MarkupPalette,
+    traits::color::Color,
+};
+
+//A prefab is a reusable block of cells drawn as rows of legend chars, stored as json like
+//
+//  {
+//      "name": "table",
+//      "rows": ["+-+", "|.|"],
+//      "legend": {
+//          "+": { "glyph": "corner", "foreground": "brown" },
+//          ".": null
+//      }
+//  }
+//
+//Legend entries of null are transparent and leave whatever is under the prefab when it is placed
+//Chars without a legend entry use the charmap's glyph for that char with the template's colours
+
+//A glyph referred to either by its index in the font or by its name in the charmap
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(untagged))]
+pub enum GlyphRef {
+    Index(usize),
+    Name(String),
+}
+
+impl GlyphRef {
+    pub fn resolve(&self, charmap: &CharMap) -> Option<usize> {
+        match self {
+            GlyphRef::Index(index) => Some(*index),
+            GlyphRef::Name(name) => charmap.glyph(name),
+        }
+    }
+}
+
+//A single legend entry, colours are palette names or hex colours and anything left out comes from the template
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct PrefabCell {
+    pub glyph: Option<GlyphRef>,
+    pub foreground: Option<String>,
+    //Some("none") removes the template's background
+    pub background: Option<String>,
+    pub rotation: Option<CharRotation>,
+    pub mirror: Option<CharMirror>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct Prefab {
+    pub name: String,
+    pub rows: Vec<String>,
+    pub legend: HashMap<char, Option<PrefabCell>>,
+}
+
+#[derive(Debug)]
+pub enum PrefabError {
+    UnknownGlyph(String, GlyphRef),
+    UnknownChar(String, char),
+    UnknownColor(String, String),
+    DuplicateName(String),
+    #[cfg(feature = "prefabs")]
+    Io(io::Error),
+    #[cfg(feature = "prefabs")]
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for PrefabError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefabError::UnknownGlyph(prefab, glyph) => {
+                write!(f, "unknown glyph {:?} in prefab '{}'", glyph, prefab)
+            }
+            PrefabError::UnknownChar(prefab, ch) => {
+                write!(
+                    f,
+                    "no glyph or legend entry for '{}' in prefab '{}'",
+                    ch, prefab
+                )
+            }
+            PrefabError::UnknownColor(prefab, color) => {
+                write!(f, "unknown color '{}' in prefab '{}'", color, prefab)
+            }
+            PrefabError::DuplicateName(prefab) => write!(f, "duplicate prefab name '{}'", prefab),
+            #[cfg(feature = "prefabs")]
+            PrefabError::Io(error) => write!(f, "couldn't read prefab: {}", error),
+            #[cfg(feature = "prefabs")]
+            PrefabError::Parse(error) => write!(f, "couldn't parse prefab: {}", error),
+        }
+    }
+}
+
+impl Error for PrefabError {}
+
+#[cfg(feature = "prefabs")]
+impl From<io::Error> for PrefabError {
+    fn from(error: io::Error) -> Self {
+        PrefabError::Io(error)
+    }
+}
+
+#[cfg(feature = "prefabs")]
+impl From<serde_json::Error> for PrefabError {
+    fn from(error: serde_json::Error) -> Self {
+        PrefabError::Parse(error)
+    }
+}
+
+impl Prefab {
+    //The width of the longest row and the number of rows, shorter rows are padded with transparent cells
+    pub fn dimensions(&self) -> (usize, usize) {
+        let width = self
+            .rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        (width, self.rows.len())
+    }
+
+    //Turns the prefab into a grid of cells, transparent cells are left empty
+    pub fn resolve<C>(
+        &self,
+        charmap: &CharMap,
+        palette: &MarkupPalette<C>,
+        template: BunnyChar<C>,
+    ) -> Result<BunnyGrid<C>, PrefabError>
+    where
+        C: Color,
+    {
+        let mut grid = BunnyGrid::new(self.dimensions());
+
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, ch) in row.chars().enumerate() {
+                let cell = match self.legend.get(&ch) {
+                    Some(Some(cell)) => Some(self.resolve_cell(cell, charmap, palette, template)?),
+                    Some(None) => None,
+                    None => {
+                        let index = charmap
+                            .exact_index_of(ch)
+                            .ok_or_else(|| PrefabError::UnknownChar(self.name.clone(), ch))?;
+
+                        Some(template.index(index))
+                    }
+                };
+
+                grid.set(x, y, cell);
+            }
+        }
+
+        Ok(grid)
+    }
+
+    fn resolve_cell<C>(
+        &self,
+        cell: &PrefabCell,
+        charmap: &CharMap,
+        palette: &MarkupPalette<C>,
+        template: BunnyChar<C>,
+    ) -> Result<BunnyChar<C>, PrefabError>
+    where
+        C: Color,
+    {
+        let color = |name: &str| {
+            palette
+                .color(name)
+                .ok_or_else(|| PrefabError::UnknownColor(self.name.clone(), name.to_string()))
+        };
+
+        let index = match &cell.glyph {
+            Some(glyph) => glyph
+                .resolve(charmap)
+                .ok_or_else(|| PrefabError::UnknownGlyph(self.name.clone(), glyph.clone()))?,
+            None => template.index,
+        };
+
+        let foreground = match &cell.foreground {
+            Some(name) => color(name)?,
+            None => template.foreground,
+        };
+
+        let background = match cell.background.as_deref() {
+            Some("none") => None,
+            Some(name) => Some(color(name)?),
+            None => template.background,
+        };
+
+        Ok(BunnyChar {
+            index,
+            foreground,
+            background,
+            rotation: cell.rotation.unwrap_or(template.rotation),
+            mirror: cell.mirror.unwrap_or(template.mirror),
+        })
+    }
+
+    #[cfg(feature = "prefabs")]
+    pub fn from_json(json: &str) -> Result<Self, PrefabError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    #[cfg(feature = "prefabs")]
+    pub fn to_json(&self) -> Result<String, PrefabError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+//A set of resolved prefabs looked up by name
+#[derive(Clone, Debug)]
+pub struct PrefabLibrary<C> {
+    prefabs: HashMap<String, BunnyGrid<C>>,
+}
+
+impl<C> Default for PrefabLibrary<C> {
+    fn default() -> Self {
+        Self {
+            prefabs: HashMap::new(),
+        }
+    }
+}
+
+impl<C> PrefabLibrary<C>
+where
+    C: Color,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //Loads every .json file in a directory, each file holds either a single prefab or a list of them
+    #[cfg(feature = "prefabs")]
+    pub fn load_dir<P>(
+        path: P,
+        charmap: &CharMap,
+        palette: &MarkupPalette<C>,
+        template: BunnyChar<C>,
+    ) -> Result<Self, PrefabError>
+    where
+        P: AsRef<Path>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum PrefabFile {
+            Single(Prefab),
+            Many(Vec<Prefab>),
+        }
+
+        let mut paths = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        //Sorted so that which file a duplicate is reported in doesn't depend on the filesystem
+        paths.sort();
+
+        let mut library = Self::new();
+
+        for path in paths {
+            if !path.is_file() || path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+
+            let prefabs = match serde_json::from_str(&fs::read_to_string(&path)?)? {
+                PrefabFile::Single(prefab) => vec![prefab],
+                PrefabFile::Many(prefabs) => prefabs,
+            };
+
+            for mut prefab in prefabs {
+                //Unnamed prefabs are named after their file
+                if prefab.name.is_empty() {
+                    if let Some(stem) = path.file_stem() {
+                        prefab.name = stem.to_string_lossy().into_owned();
+                    }
+                }
+
+                library.load(&prefab, charmap, palette, template)?;
+            }
+        }
+
+        Ok(library)
+    }
+
+    //Resolves and adds a prefab, names must be unique
+    pub fn load(
+        &mut self,
+        prefab: &Prefab,
+        charmap: &CharMap,
+        palette: &MarkupPalette<C>,
+        template: BunnyChar<C>,
+    ) -> Result<(), PrefabError> {
+        if self.prefabs.contains_key(&prefab.name) {
+            return Err(PrefabError::DuplicateName(prefab.name.clone()));
+        }
+
+        let grid = prefab.resolve(charmap, palette, template)?;
+        self.prefabs.insert(prefab.name.clone(), grid);
+
+        Ok(())
+    }
+
+    //Adds an already built block of cells, replacing any prefab with the same name
+    pub fn insert(&mut self, name: &str, cells: BunnyGrid<C>) {
+        self.prefabs.insert(name.to_string(), cells);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BunnyGrid<C>> {
+        self.prefabs.get(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.prefabs.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.prefabs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefabs.is_empty()
+    }
+
+    //Stamps a prefab onto a grid with its top left corner at the given position after rotating and mirroring it
+    //Transparent cells leave the grid untouched and anything outside of the grid is clipped, returns false if there is no such prefab
+    pub fn place(
+        &self,
+        grid: &mut BunnyGrid<C>,
+        name: &str,
+        position: (i32, i32),
+        rotation: CharRotation,
+        mirror: CharMirror,
+    ) -> bool {
+        match self.prefabs.get(name) {
+            Some(prefab) => {
+                grid.paste_region(&prefab.oriented(rotation, mirror), position, true);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::color::TestColor;
+
+    const WHITE: TestColor = TestColor([255; 4]);
+    const BLACK: TestColor = TestColor([0, 0, 0, 255]);
+
+    fn template() -> BunnyChar<TestColor> {
+        BunnyChar::new(0, WHITE, Some(BLACK), CharRotation::None, CharMirror::None)
+    }
+
+    fn charmap() -> CharMap {
+        CharMap::cp437().with_name("corner", 0xc5)
+    }
+
+    fn table() -> Prefab {
+        let mut legend = HashMap::new();
+
+        legend.insert(
+            '+',
+            Some(PrefabCell {
+                glyph: Some(GlyphRef::Name("corner".to_string())),
+                foreground: Some("red".to_string()),
+                background: Some("none".to_string()),
+                ..PrefabCell::default()
+            }),
+        );
+        legend.insert('.', None);
+
+        Prefab {
+            name: "table".to_string(),
+            rows: vec!["+-+".to_string(), "|.".to_string()],
+            legend,
+        }
+    }
+
+    fn resolve(prefab: &Prefab) -> Result<BunnyGrid<TestColor>, PrefabError> {
+        prefab.resolve(&charmap(), &MarkupPalette::basic(), template())
+    }
+
+    #[test]
+    fn legend_entries_override_the_template() {
+        let grid = resolve(&table()).unwrap();
+        let red = MarkupPalette::<TestColor>::basic().color("red").unwrap();
+
+        assert_eq!(grid.dimensions(), (3, 2));
+        assert_eq!(
+            grid.get(0, 0),
+            Some(&BunnyChar::new(
+                0xc5,
+                red,
+                None,
+                CharRotation::None,
+                CharMirror::None
+            ))
+        );
+        assert_eq!(grid.get(1, 0), Some(&template().index(0x2d)));
+        assert_eq!(grid.get(0, 1), Some(&template().index(0x7c)));
+        assert_eq!(grid.get(1, 1), None);
+        assert_eq!(grid.get(2, 1), None);
+    }
+
+    #[test]
+    fn unresolvable_cells_are_errors() {
+        let mut prefab = table();
+        prefab.rows.push("✓".to_string());
+        assert!(matches!(
+            resolve(&prefab),
+            Err(PrefabError::UnknownChar(_, '✓'))
+        ));
+
+        let mut prefab = table();
+        prefab.legend.insert(
+            '-',
+            Some(PrefabCell {
+                glyph: Some(GlyphRef::Name("leg".to_string())),
+                ..PrefabCell::default()
+            }),
+        );
+        assert!(matches!(
+            resolve(&prefab),
+            Err(PrefabError::UnknownGlyph(_, GlyphRef::Name(_)))
+        ));
+
+        let mut prefab = table();
+        prefab.legend.insert(
+            '-',
+            Some(PrefabCell {
+                background: Some("#12".to_string()),
+                ..PrefabCell::default()
+            }),
+        );
+        assert!(matches!(
+            resolve(&prefab),
+            Err(PrefabError::UnknownColor(_, _))
+        ));
+    }
+
+    #[test]
+    fn placing_orients_and_clips_prefabs() {
+        let mut library = PrefabLibrary::new();
+        library
+            .load(&table(), &charmap(), &MarkupPalette::basic(), template())
+            .unwrap();
+
+        let mut grid = BunnyGrid::new((3, 3));
+        grid.fill(Some(template().index(9)));
+
+        assert!(library.place(
+            &mut grid,
+            "table",
+            (1, 1),
+            CharRotation::Rotation90,
+            CharMirror::None
+        ));
+        assert!(!library.place(
+            &mut grid,
+            "chair",
+            (0, 0),
+            CharRotation::None,
+            CharMirror::None
+        ));
+
+        let index = |x, y| grid.get(x, y).map(|cell| (cell.index, cell.rotation));
+
+        assert_eq!(index(1, 1), Some((0x7c, CharRotation::Rotation90)));
+        assert_eq!(index(2, 1), Some((0xc5, CharRotation::Rotation90)));
+        assert_eq!(index(2, 2), Some((0x2d, CharRotation::Rotation90)));
+        assert_eq!(index(1, 2), Some((9, CharRotation::None)));
+        assert_eq!(index(0, 0), Some((9, CharRotation::None)));
+    }
+
+    #[test]
+    fn names_must_be_unique() {
+        let mut library = PrefabLibrary::new();
+        let load = |library: &mut PrefabLibrary<TestColor>| {
+            library.load(&table(), &charmap(), &MarkupPalette::basic(), template())
+        };
+
+        assert!(load(&mut library).is_ok());
+        assert!(matches!(
+            load(&mut library),
+            Err(PrefabError::DuplicateName(_))
+        ));
+        assert_eq!(library.names().collect::<Vec<_>>(), vec!["table"]);
+    }
+
+    #[cfg(feature = "prefabs")]
+    #[test]
+    fn json_round_trips() {
+        let prefab = Prefab::from_json(
+            r#"{
+                "name": "table",
+                "rows": ["+-+", "|."],
+                "legend": {
+                    "+": { "glyph": "corner", "foreground": "red", "background": "none" },
+                    ".": null
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(prefab, table());
+        assert_eq!(
+            Prefab::from_json(&prefab.to_json().unwrap()).unwrap(),
+            prefab
+        );
+
+        let indexed = Prefab::from_json(r#"{ "legend": { "x": { "glyph": 3 } } }"#).unwrap();
+        assert_eq!(
+            indexed.legend[&'x'].as_ref().unwrap().glyph,
+            Some(GlyphRef::Index(3))
+        );
+    }
+
+    #[cfg(feature = "prefabs")]
+    #[test]
+    fn directories_are_loaded_by_file() {
+        let dir = std::env::temp_dir().join(format!("bunnyfont-prefabs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(dir.join("stool.json"), r#"{ "rows": ["o"] }"#).unwrap();
+        fs::write(
+            dir.join("many.json"),
+            r#"[{ "name": "a", "rows": ["-"] }, { "name": "b", "rows": ["|"] }]"#,
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "not a prefab").unwrap();
+
+        let library =
+            PrefabLibrary::load_dir(&dir, &charmap(), &MarkupPalette::basic(), template());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let library = library.unwrap();
+        let mut names: Vec<&str> = library.names().collect();
+        names.sort_unstable();
+
+        assert_eq!(names, vec!["a", "b", "stool"]);
+        assert_eq!(library.get("stool").unwrap().get(0, 0).unwrap().index, 0x6f);
+    }
+}
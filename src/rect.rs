@@ -0,0 +1,113 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+//A rectangular region of a grid, in cells
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GridRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl GridRect {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn from_dimensions(dimensions: (usize, usize)) -> Self {
+        let (width, height) = dimensions;
+
+        Self::new(0, 0, width, height)
+    }
+
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    //One past the rightmost column
+    pub fn right(&self) -> usize {
+        self.x + self.width
+    }
+
+    //One past the bottom row
+    pub fn bottom(&self) -> usize {
+        self.y + self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && y >= self.y && x < self.right() && y < self.bottom()
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right()).max(x);
+        let bottom = self.bottom().min(other.bottom()).max(y);
+
+        Self::new(x, y, right - x, bottom - y)
+    }
+
+    //Shrinks the rect by the given number of cells on every side
+    pub fn inset(&self, amount: usize) -> Self {
+        let width = self.width.saturating_sub(amount * 2);
+        let height = self.height.saturating_sub(amount * 2);
+
+        Self::new(
+            self.x + amount.min(self.width / 2),
+            self.y + amount.min(self.height / 2),
+            width,
+            height,
+        )
+    }
+
+    //Iterates over every cell position in the rect in row-major order
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> {
+        let rect = *self;
+
+        (rect.y..rect.bottom()).flat_map(move |y| (rect.x..rect.right()).map(move |x| (x, y)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersection_of_disjoint_rects_is_empty() {
+        let a = GridRect::new(0, 0, 2, 2);
+        let b = GridRect::new(5, 5, 2, 2);
+
+        assert!(a.intersection(&b).is_empty());
+        assert_eq!(
+            a.intersection(&GridRect::new(1, 1, 5, 5)),
+            GridRect::new(1, 1, 1, 1)
+        );
+    }
+
+    #[test]
+    fn inset_never_goes_negative() {
+        assert_eq!(
+            GridRect::new(1, 1, 6, 4).inset(1),
+            GridRect::new(2, 2, 4, 2)
+        );
+        assert!(GridRect::new(0, 0, 3, 3).inset(2).is_empty());
+    }
+
+    #[test]
+    fn cells_are_row_major() {
+        let cells: Vec<_> = GridRect::new(1, 2, 2, 2).cells().collect();
+
+        assert_eq!(cells, vec![(1, 2), (2, 2), (1, 3), (2, 3)]);
+    }
+}
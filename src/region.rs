@@ -0,0 +1,242 @@
+use crate::{
+    char_transforms::{CharMirror, CharRotation},
+    grid::BunnyGrid,
+    rect::GridRect,
+    traits::color::Color,
+};
+
+impl<C> BunnyGrid<C>
+where
+    C: Color,
+{
+    //Copies an area of the grid into a new grid, the area is clipped to the grid
+    pub fn copy_region(&self, area: GridRect) -> Self {
+        let area = area.intersection(&GridRect::from_dimensions(self.dimensions()));
+        let mut region = Self::new(area.dimensions());
+
+        for (x, y) in area.cells() {
+            region.set(x - area.x, y - area.y, self.get(x, y).copied());
+        }
+
+        region
+    }
+
+    //Pastes another grid with its top left corner at the given position, clipped to this grid
+    //When transparent, empty cells of the pasted grid leave the cells under them untouched
+    pub fn paste_region(&mut self, region: &Self, position: (i32, i32), transparent: bool) {
+        let (position_x, position_y) = position;
+
+        for ((x, y), cell) in region.cells() {
+            if cell.is_some() || !transparent {
+                self.set_clipped(position_x + x as i32, position_y + y as i32, cell.copied());
+            }
+        }
+    }
+
+    //A copy of the grid rotated clockwise, with every char rotated along with it
+    pub fn rotated(&self, rotation: CharRotation) -> Self {
+        let (width, height) = self.dimensions();
+
+        let dimensions = match rotation {
+            CharRotation::None | CharRotation::Rotation180 => (width, height),
+            CharRotation::Rotation90 | CharRotation::Rotation270 => (height, width),
+        };
+
+        let mut rotated = Self::new(dimensions);
+
+        for ((x, y), cell) in self.cells() {
+            let (rotated_x, rotated_y) = match rotation {
+                CharRotation::None => (x, y),
+                CharRotation::Rotation90 => (height - 1 - y, x),
+                CharRotation::Rotation180 => (width - 1 - x, height - 1 - y),
+                CharRotation::Rotation270 => (y, width - 1 - x),
+            };
+
+            let cell = cell.map(|cell| cell.oriented(rotation, CharMirror::None));
+            rotated.set(rotated_x, rotated_y, cell);
+        }
+
+        rotated
+    }
+
+    //A copy of the grid mirrored, with every char mirrored along with it
+    pub fn mirrored(&self, mirror: CharMirror) -> Self {
+        let (width, height) = self.dimensions();
+        let mut mirrored = Self::new((width, height));
+
+        for ((x, y), cell) in self.cells() {
+            let (mirrored_x, mirrored_y) = match mirror {
+                CharMirror::None => (x, y),
+                CharMirror::MirrorX => (width - 1 - x, y),
+                CharMirror::MirrorY => (x, height - 1 - y),
+                CharMirror::MirrorBoth => (width - 1 - x, height - 1 - y),
+            };
+
+            let cell = cell.map(|cell| cell.oriented(CharRotation::None, mirror));
+            mirrored.set(mirrored_x, mirrored_y, cell);
+        }
+
+        mirrored
+    }
+
+    //A copy of the grid rotated and then mirrored, the same order chars are oriented in
+    pub fn oriented(&self, rotation: CharRotation, mirror: CharMirror) -> Self {
+        self.rotated(rotation).mirrored(mirror)
+    }
+
+    //Rotates an area of the grid in place around its top left corner
+    //Rotating a non-square area by 90 or 270 degrees changes its footprint, cells it no longer covers are cleared
+    pub fn rotate_region(&mut self, area: GridRect, rotation: CharRotation) {
+        self.transform_region(area, rotation, CharMirror::None);
+    }
+
+    pub fn mirror_region(&mut self, area: GridRect, mirror: CharMirror) {
+        self.transform_region(area, CharRotation::None, mirror);
+    }
+
+    pub fn transform_region(&mut self, area: GridRect, rotation: CharRotation, mirror: CharMirror) {
+        let region = self.copy_region(area).oriented(rotation, mirror);
+
+        for (x, y) in area.cells() {
+            self.set_clipped(x as i32, y as i32, None);
+        }
+
+        self.paste_region(&region, (area.x as i32, area.y as i32), false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{char::BunnyChar, char_transforms::compose_orientations, traits::color::TestColor};
+
+    fn cell(index: usize) -> Option<BunnyChar<TestColor>> {
+        Some(BunnyChar::new(
+            index,
+            TestColor([255; 4]),
+            None,
+            CharRotation::None,
+            CharMirror::None,
+        ))
+    }
+
+    //A grid with each cell's index counting up in row order from 1
+    fn numbered(dimensions: (usize, usize)) -> BunnyGrid<TestColor> {
+        let mut grid = BunnyGrid::new(dimensions);
+
+        for (x, y) in GridRect::from_dimensions(dimensions).cells() {
+            grid.set(x, y, cell(y * dimensions.0 + x + 1));
+        }
+
+        grid
+    }
+
+    fn indices(grid: &BunnyGrid<TestColor>) -> Vec<Option<usize>> {
+        grid.cells()
+            .map(|(_, cell)| cell.map(|cell| cell.index))
+            .collect()
+    }
+
+    #[test]
+    fn copied_regions_are_clipped_to_the_grid() {
+        let region = numbered((3, 2)).copy_region(GridRect::new(1, 0, 5, 5));
+
+        assert_eq!(region.dimensions(), (2, 2));
+        assert_eq!(indices(&region), vec![Some(2), Some(3), Some(5), Some(6)]);
+    }
+
+    #[test]
+    fn transparent_pastes_keep_cells_under_empty_cells() {
+        let mut region = numbered((2, 1));
+        region.set(0, 0, None);
+
+        let mut grid = BunnyGrid::new((3, 1));
+        grid.fill(cell(9));
+        grid.paste_region(&region, (1, 0), true);
+        assert_eq!(indices(&grid), vec![Some(9), Some(9), Some(2)]);
+
+        grid.paste_region(&region, (-1, 0), false);
+        assert_eq!(indices(&grid), vec![Some(2), Some(9), Some(2)]);
+
+        grid.paste_region(&region, (0, 0), false);
+        assert_eq!(indices(&grid), vec![None, Some(2), Some(2)]);
+    }
+
+    #[test]
+    fn rotating_moves_and_rotates_cells() {
+        let rotated = numbered((3, 2)).rotated(CharRotation::Rotation90);
+
+        assert_eq!(rotated.dimensions(), (2, 3));
+        assert_eq!(
+            indices(&rotated),
+            vec![Some(4), Some(1), Some(5), Some(2), Some(6), Some(3)]
+        );
+        assert!(rotated
+            .cells()
+            .all(|(_, cell)| cell.unwrap().rotation == CharRotation::Rotation90));
+
+        let grid = numbered((3, 2));
+        let turned = grid
+            .rotated(CharRotation::Rotation90)
+            .rotated(CharRotation::Rotation180)
+            .rotated(CharRotation::Rotation90);
+
+        let cells = |grid: &BunnyGrid<TestColor>| -> Vec<Option<BunnyChar<TestColor>>> {
+            grid.cells().map(|(_, cell)| cell.copied()).collect()
+        };
+
+        assert_eq!(turned.dimensions(), grid.dimensions());
+        assert_eq!(cells(&turned), cells(&grid));
+    }
+
+    #[test]
+    fn mirroring_moves_and_mirrors_cells() {
+        let mirrored = numbered((3, 2)).mirrored(CharMirror::MirrorX);
+
+        assert_eq!(
+            indices(&mirrored),
+            vec![Some(3), Some(2), Some(1), Some(6), Some(5), Some(4)]
+        );
+        assert!(mirrored
+            .cells()
+            .all(|(_, cell)| cell.unwrap().mirror == CharMirror::MirrorX));
+
+        let oriented = numbered((3, 2)).oriented(CharRotation::Rotation90, CharMirror::MirrorY);
+        let cell = oriented.get(0, 0).unwrap();
+
+        assert_eq!(cell.index, 6);
+        assert_eq!(
+            (cell.rotation, cell.mirror),
+            compose_orientations(
+                (CharRotation::None, CharMirror::None),
+                (CharRotation::Rotation90, CharMirror::MirrorY)
+            )
+        );
+    }
+
+    #[test]
+    fn rotating_a_non_square_region_clears_what_it_no_longer_covers() {
+        let mut grid = numbered((3, 3));
+        grid.mark_clean();
+
+        grid.rotate_region(GridRect::new(0, 0, 2, 1), CharRotation::Rotation90);
+
+        assert_eq!(
+            indices(&grid),
+            vec![
+                Some(1),
+                None,
+                Some(3),
+                Some(2),
+                Some(5),
+                Some(6),
+                Some(7),
+                Some(8),
+                Some(9)
+            ]
+        );
+        assert!(grid.is_dirty(1, 0));
+        assert!(grid.is_dirty(0, 1));
+        assert!(!grid.is_dirty(2, 2));
+    }
+}
@@ -0,0 +1,592 @@
+use std::{collections::VecDeque, ops::RangeInclusive};
+
+use crate::{char::BunnyChar, charmap::CharMap, grid::BunnyGrid, traits::color::Color};
+
+//The cells of a line between two cells, including both ends
+pub fn line_points(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    //Working in i64 keeps the doubled error from overflowing on lines spanning the whole i32 range
+    let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+    let (to_x, to_y) = (to.0 as i64, to.1 as i64);
+
+    let (dx, dy) = ((to_x - x).abs(), -(to_y - y).abs());
+    let (step_x, step_y) = ((to_x - x).signum(), (to_y - y).signum());
+    let mut error = dx + dy;
+
+    let mut points = Vec::new();
+
+    loop {
+        points.push((x as i32, y as i32));
+
+        if (x, y) == (to_x, to_y) {
+            return points;
+        }
+
+        let doubled = error * 2;
+
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+//Clips a line to the cells of a grid, or None if none of it is inside
+pub(crate) fn clip_line(
+    from: (i32, i32),
+    to: (i32, i32),
+    dimensions: (usize, usize),
+) -> Option<((i32, i32), (i32, i32))> {
+    let (width, height) = dimensions;
+
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let (from_x, from_y) = (from.0 as f64, from.1 as f64);
+    let (dx, dy) = (to.0 as f64 - from_x, to.1 as f64 - from_y);
+
+    //Liang-Barsky, each edge limits how far along the line the inside part can start or end
+    let edges = [
+        (-dx, from_x),
+        (dx, (width - 1) as f64 - from_x),
+        (-dy, from_y),
+        (dy, (height - 1) as f64 - from_y),
+    ];
+
+    let (mut start, mut end) = (0.0f64, 1.0f64);
+
+    for (p, q) in edges {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            start = start.max(q / p);
+        } else {
+            end = end.min(q / p);
+        }
+    }
+
+    if start > end {
+        return None;
+    }
+
+    let point = |t: f64| {
+        (
+            (from_x + t * dx).round() as i32,
+            (from_y + t * dy).round() as i32,
+        )
+    };
+
+    Some((point(start), point(end)))
+}
+
+//The cells of an axis aligned ellipse, either its outline or every cell inside it
+pub fn ellipse_points(centre: (i32, i32), radii: (i32, i32), filled: bool) -> Vec<(i32, i32)> {
+    //Cells that don't fit in an i32 are left out
+    let (min, max) = (i32::MIN as i64, i32::MAX as i64);
+
+    ellipse_spans(centre, radii, filled, min..=max)
+        .into_iter()
+        .flat_map(|(y, from_x, to_x)| {
+            (from_x.max(min)..=to_x.min(max)).map(move |x| (x as i32, y as i32))
+        })
+        .collect()
+}
+
+//The runs of cells of an ellipse in the given rows as (y, first x, last x), in row order
+//A cell is inside when its centre is inside the ellipse grown by half a cell, and the outline is
+//the inside cells that have an outside cell next to them
+fn ellipse_spans(
+    centre: (i32, i32),
+    radii: (i32, i32),
+    filled: bool,
+    rows: RangeInclusive<i64>,
+) -> Vec<(i64, i64, i64)> {
+    let (centre_x, centre_y) = (centre.0 as i64, centre.1 as i64);
+    let (radius_x, radius_y) = (radii.0.unsigned_abs() as i64, radii.1.unsigned_abs() as i64);
+    let (grown_x, grown_y) = (radius_x as f64 + 0.5, radius_y as f64 + 0.5);
+
+    let half_width = |dy: i64| {
+        if dy > radius_y {
+            -1
+        } else {
+            (grown_x * (1.0 - (dy as f64 / grown_y).powi(2)).sqrt()) as i64
+        }
+    };
+
+    let top = (centre_y - radius_y).max(*rows.start());
+    let bottom = (centre_y + radius_y).min(*rows.end());
+
+    let mut spans = Vec::new();
+
+    for y in top..=bottom {
+        let dy = (y - centre_y).abs();
+        let outer = half_width(dy);
+
+        let inner = if filled {
+            0
+        } else {
+            (half_width(dy + 1) + 1).min(outer)
+        };
+
+        if inner == 0 {
+            spans.push((y, centre_x - outer, centre_x + outer));
+        } else {
+            spans.push((y, centre_x - outer, centre_x - inner));
+            spans.push((y, centre_x + inner, centre_x + outer));
+        }
+    }
+
+    spans
+}
+
+//The cells of a closed polygon, either its outline or every cell inside it by the even-odd rule
+pub fn polygon_points(vertices: &[(i32, i32)], filled: bool) -> Vec<(i32, i32)> {
+    let mut points: Vec<(i32, i32)> = polygon_edges(vertices)
+        .flat_map(|(from, to)| line_points(from, to))
+        .collect();
+
+    if filled {
+        for (y, from_x, to_x) in polygon_spans(vertices, i32::MIN as i64..=i32::MAX as i64) {
+            for x in from_x..=to_x {
+                points.push((x as i32, y as i32));
+            }
+        }
+    }
+
+    points.sort_unstable_by_key(|(x, y)| (*y, *x));
+    points.dedup();
+    points
+}
+
+fn polygon_edges(vertices: &[(i32, i32)]) -> impl Iterator<Item = ((i32, i32), (i32, i32))> + '_ {
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(from, to)| (*from, *to))
+}
+
+//The runs of cells inside a polygon in the given rows as (y, first x, last x)
+fn polygon_spans(vertices: &[(i32, i32)], rows: RangeInclusive<i64>) -> Vec<(i64, i64, i64)> {
+    let mut spans = Vec::new();
+
+    if vertices.len() < 3 {
+        return spans;
+    }
+
+    let top = vertices.iter().map(|(_, y)| *y as i64).min().unwrap();
+    let bottom = vertices.iter().map(|(_, y)| *y as i64).max().unwrap();
+
+    for y in top.max(*rows.start())..=bottom.min(*rows.end()) {
+        //Sampling through the middle of each row avoids counting shared vertices twice
+        let scan_y = y as f64 + 0.5;
+
+        let mut crossings: Vec<f64> = polygon_edges(vertices)
+            .filter_map(|((from_x, from_y), (to_x, to_y))| {
+                let (from_y, to_y) = (from_y as f64, to_y as f64);
+
+                if (from_y <= scan_y) != (to_y <= scan_y) {
+                    let t = (scan_y - from_y) / (to_y - from_y);
+                    Some(from_x as f64 + t * (to_x as f64 - from_x as f64))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        crossings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        for pair in crossings.chunks_exact(2) {
+            spans.push((y, pair[0].round() as i64, pair[1].round() as i64));
+        }
+    }
+
+    spans
+}
+
+//Glyphs for drawing lines in each direction, with y increasing downwards
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LineGlyphs {
+    pub horizontal: usize,
+    pub vertical: usize,
+    //Runs from top left to bottom right, like '\'
+    pub falling: usize,
+    //Runs from bottom left to top right, like '/'
+    pub rising: usize,
+}
+
+impl LineGlyphs {
+    pub fn from_charmap(charmap: &CharMap) -> Option<Self> {
+        Some(Self {
+            horizontal: charmap.exact_index_of('-')?,
+            vertical: charmap.exact_index_of('|')?,
+            falling: charmap.exact_index_of('\\')?,
+            rising: charmap.exact_index_of('/')?,
+        })
+    }
+
+    fn for_step(&self, dx: i32, dy: i32) -> usize {
+        match (dx.signum(), dy.signum()) {
+            (_, 0) => self.horizontal,
+            (0, _) => self.vertical,
+            (x, y) if x == y => self.falling,
+            _ => self.rising,
+        }
+    }
+}
+
+//What a shape is drawn with, either a single char or glyphs picked by the direction of each step of a line
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Brush<C> {
+    Char(BunnyChar<C>),
+    Directional(BunnyChar<C>, LineGlyphs),
+}
+
+impl<C> Brush<C>
+where
+    C: Color,
+{
+    fn template(&self) -> BunnyChar<C> {
+        match self {
+            Brush::Char(template) | Brush::Directional(template, _) => *template,
+        }
+    }
+}
+
+impl<C> BunnyGrid<C>
+where
+    C: Color,
+{
+    //Draws a run of connected cells, directional brushes pick each cell's glyph from its neighbours
+    fn draw_path(&mut self, points: &[(i32, i32)], brush: &Brush<C>) {
+        for (index, (x, y)) in points.iter().enumerate() {
+            let cell = match brush {
+                Brush::Char(template) => *template,
+                Brush::Directional(template, glyphs) => {
+                    let previous = index.checked_sub(1).map(|previous| points[previous]);
+                    let next = points.get(index + 1).copied();
+
+                    let (dx, dy) = match (previous, next) {
+                        (_, Some((next_x, next_y))) => (next_x - x, next_y - y),
+                        (Some((previous_x, previous_y)), None) => (x - previous_x, y - previous_y),
+                        (None, None) => (1, 0),
+                    };
+
+                    template.index(glyphs.for_step(dx, dy))
+                }
+            };
+
+            self.set_clipped(*x, *y, Some(cell));
+        }
+    }
+
+    //Fills runs of cells, clipping them to the grid
+    fn draw_spans(&mut self, spans: &[(i64, i64, i64)], cell: BunnyChar<C>) {
+        let width = self.dimensions().0 as i64;
+
+        for (y, from_x, to_x) in spans {
+            for x in (*from_x).max(0)..=(*to_x).min(width - 1) {
+                self.set(x as usize, *y as usize, Some(cell));
+            }
+        }
+    }
+
+    pub fn draw_line(&mut self, from: (i32, i32), to: (i32, i32), brush: &Brush<C>) {
+        if let Some((from, to)) = clip_line(from, to, self.dimensions()) {
+            self.draw_path(&line_points(from, to), brush);
+        }
+    }
+
+    //Draws a rectangle between two opposite corners, inclusive
+    pub fn draw_rect(&mut self, from: (i32, i32), to: (i32, i32), brush: &Brush<C>, filled: bool) {
+        let (left, right) = (from.0.min(to.0), from.0.max(to.0));
+        let (top, bottom) = (from.1.min(to.1), from.1.max(to.1));
+
+        if filled {
+            let (width, height) = self.dimensions();
+
+            for y in top.max(0)..=bottom.min(height as i32 - 1) {
+                for x in left.max(0)..=right.min(width as i32 - 1) {
+                    self.set(x as usize, y as usize, Some(brush.template()));
+                }
+            }
+        } else {
+            self.draw_line((left, top), (right, top), brush);
+            self.draw_line((left, bottom), (right, bottom), brush);
+            self.draw_line((left, top), (left, bottom), brush);
+            self.draw_line((right, top), (right, bottom), brush);
+        }
+    }
+
+    pub fn draw_circle(
+        &mut self,
+        centre: (i32, i32),
+        radius: i32,
+        cell: BunnyChar<C>,
+        filled: bool,
+    ) {
+        self.draw_ellipse(centre, (radius, radius), cell, filled);
+    }
+
+    pub fn draw_ellipse(
+        &mut self,
+        centre: (i32, i32),
+        radii: (i32, i32),
+        cell: BunnyChar<C>,
+        filled: bool,
+    ) {
+        let height = self.dimensions().1 as i64;
+        self.draw_spans(&ellipse_spans(centre, radii, filled, 0..=height - 1), cell);
+    }
+
+    pub fn draw_polygon(&mut self, vertices: &[(i32, i32)], brush: &Brush<C>, filled: bool) {
+        if filled {
+            let height = self.dimensions().1 as i64;
+            self.draw_spans(&polygon_spans(vertices, 0..=height - 1), brush.template());
+        }
+
+        for (from, to) in polygon_edges(vertices) {
+            self.draw_line(from, to, brush);
+        }
+    }
+
+    //Replaces the 4-connected region of cells identical to the starting cell
+    pub fn flood_fill(&mut self, x: usize, y: usize, cell: Option<BunnyChar<C>>) {
+        let (width, height) = self.dimensions();
+
+        if x >= width || y >= height {
+            return;
+        }
+
+        let target = self.get(x, y).copied();
+
+        if target == cell {
+            return;
+        }
+
+        let mut queue = VecDeque::new();
+        queue.push_back((x, y));
+        self.set(x, y, cell);
+
+        while let Some((x, y)) = queue.pop_front() {
+            let neighbours = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+
+            for (x, y) in neighbours {
+                if x < width && y < height && self.get(x, y).copied() == target {
+                    self.set(x, y, cell);
+                    queue.push_back((x, y));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        char_transforms::{CharMirror, CharRotation},
+        traits::color::TestColor,
+    };
+
+    fn template() -> BunnyChar<TestColor> {
+        BunnyChar::new(
+            1,
+            TestColor([255; 4]),
+            None,
+            CharRotation::None,
+            CharMirror::None,
+        )
+    }
+
+    fn rows(grid: &BunnyGrid<TestColor>) -> Vec<String> {
+        let (width, height) = grid.dimensions();
+
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| if grid.get(x, y).is_some() { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn line_points_include_both_ends() {
+        assert_eq!(
+            line_points((0, 0), (3, 1)),
+            vec![(0, 0), (1, 0), (2, 1), (3, 1)]
+        );
+        assert_eq!(line_points((2, 2), (2, 2)), vec![(2, 2)]);
+        assert_eq!(
+            line_points((i32::MAX - 1, i32::MIN), (i32::MAX, i32::MIN)),
+            vec![(i32::MAX - 1, i32::MIN), (i32::MAX, i32::MIN)]
+        );
+    }
+
+    #[test]
+    fn lines_are_clipped_to_the_grid() {
+        let mut grid = BunnyGrid::new((4, 4));
+
+        grid.draw_line((0, 0), (i32::MAX, 1), &Brush::Char(template()));
+        assert_eq!(rows(&grid), vec!["####", "....", "....", "...."]);
+
+        grid.clear();
+        grid.draw_line(
+            (i32::MIN, i32::MIN),
+            (i32::MAX, i32::MAX),
+            &Brush::Char(template()),
+        );
+        assert_eq!(rows(&grid), vec!["#...", ".#..", "..#.", "...#"]);
+
+        grid.clear();
+        grid.draw_line((-5, -5), (-1, 10), &Brush::Char(template()));
+        assert!(!grid.cells().any(|(_, cell)| cell.is_some()));
+
+        assert_eq!(clip_line((0, 0), (3, 0), (0, 4)), None);
+        assert_eq!(clip_line((1, 1), (2, 3), (4, 4)), Some(((1, 1), (2, 3))));
+    }
+
+    #[test]
+    fn directional_brushes_follow_the_line() {
+        let charmap = CharMap::cp437();
+        let glyphs = LineGlyphs::from_charmap(&charmap).unwrap();
+        let mut grid = BunnyGrid::new((3, 3));
+
+        grid.draw_line((0, 0), (2, 2), &Brush::Directional(template(), glyphs));
+        grid.draw_line((0, 2), (1, 2), &Brush::Directional(template(), glyphs));
+
+        let index = |x, y| grid.get(x, y).map(|bunny_char| bunny_char.index);
+
+        assert_eq!(index(0, 0), Some(glyphs.falling));
+        assert_eq!(index(2, 2), Some(glyphs.falling));
+        assert_eq!(index(0, 2), Some(glyphs.horizontal));
+        assert_eq!(index(1, 2), Some(glyphs.horizontal));
+    }
+
+    #[test]
+    fn circles() {
+        let mut grid = BunnyGrid::new((5, 5));
+
+        grid.draw_circle((2, 2), 2, template(), false);
+        assert_eq!(
+            rows(&grid),
+            vec![".###.", "#...#", "#...#", "#...#", ".###."]
+        );
+
+        grid.draw_circle((2, 2), 2, template(), true);
+        assert_eq!(
+            rows(&grid),
+            vec![".###.", "#####", "#####", "#####", ".###."]
+        );
+    }
+
+    #[test]
+    fn flat_ellipses_are_lines() {
+        assert_eq!(
+            ellipse_points((1, 1), (2, 0), false),
+            vec![(-1, 1), (0, 1), (1, 1), (2, 1), (3, 1)]
+        );
+        assert_eq!(
+            ellipse_points((1, 1), (0, -1), true),
+            vec![(1, 0), (1, 1), (1, 2)]
+        );
+    }
+
+    #[test]
+    fn huge_ellipses_only_draw_rows_inside_the_grid() {
+        let mut grid = BunnyGrid::new((5, 5));
+
+        grid.draw_ellipse((0, 0), (50000, 3), template(), false);
+        assert_eq!(
+            rows(&grid),
+            vec![".....", ".....", ".....", "#####", "....."]
+        );
+
+        grid.clear();
+        grid.draw_ellipse((0, 0), (i32::MAX, i32::MAX), template(), true);
+        assert!(grid.cells().all(|(_, cell)| cell.is_some()));
+    }
+
+    #[test]
+    fn filled_rects_are_clipped_to_the_grid() {
+        let mut grid = BunnyGrid::new((3, 2));
+
+        grid.draw_rect(
+            (i32::MIN, -1),
+            (i32::MAX, 0),
+            &Brush::Char(template()),
+            true,
+        );
+        assert_eq!(rows(&grid), vec!["###", "..."]);
+
+        grid.clear();
+        grid.draw_rect((-1, -1), (1, 5), &Brush::Char(template()), false);
+        assert_eq!(rows(&grid), vec![".#.", ".#."]);
+    }
+
+    #[test]
+    fn polygons_are_filled_by_the_even_odd_rule() {
+        let triangle = [(0, 0), (4, 0), (0, 4)];
+        let mut grid = BunnyGrid::new((5, 5));
+
+        grid.draw_polygon(&triangle, &Brush::Char(template()), true);
+        assert_eq!(
+            rows(&grid),
+            vec!["#####", "####.", "###..", "##...", "#...."]
+        );
+
+        let drawn: Vec<(i32, i32)> = grid
+            .cells()
+            .filter(|(_, cell)| cell.is_some())
+            .map(|((x, y), _)| (x as i32, y as i32))
+            .collect();
+
+        assert_eq!(polygon_points(&triangle, true), drawn);
+
+        let huge = [
+            (i32::MIN, i32::MIN),
+            (i32::MAX, i32::MIN),
+            (i32::MAX, i32::MAX),
+            (i32::MIN, i32::MAX),
+        ];
+
+        grid.clear();
+        grid.draw_polygon(&huge, &Brush::Char(template()), true);
+        assert!(grid.cells().all(|(_, cell)| cell.is_some()));
+    }
+
+    #[test]
+    fn flood_fill_stops_at_different_cells() {
+        let mut grid = BunnyGrid::new((4, 3));
+        grid.draw_line((2, 0), (2, 2), &Brush::Char(template()));
+
+        grid.flood_fill(0, 0, Some(template().index(2)));
+
+        let indices: Vec<String> = (0..3)
+            .map(|y| {
+                (0..4)
+                    .map(
+                        |x| match grid.get(x, y).map(|bunny_char| bunny_char.index) {
+                            Some(index) => std::char::from_digit(index as u32, 10).unwrap(),
+                            None => '.',
+                        },
+                    )
+                    .collect()
+            })
+            .collect();
+
+        assert_eq!(indices, vec!["221.", "221.", "221."]);
+    }
+}
@@ -0,0 +1,527 @@
+use std::io::{self, Write};
+
+use crate::{
+    char::BunnyChar,
+    char_transforms::{CharMirror, CharRotation},
+    charmap::{oriented_char, CharMap},
+    font::BunnyFont,
+    formats::ansi::{
+        nearest_color, nearest_xterm_color, AnsiColorMode, OrientationFallback, VGA_PALETTE,
+    },
+    grid::BunnyGrid,
+    traits::{
+        color::Color, into_scalar::IntoScalar, lerpable::Lerpable, pixel_indexable::PixelIndexable,
+        source_image::SourceImage,
+    },
+};
+
+//Draws grids to a terminal as unicode text with escape sequences, one char per cell
+//Only the cells that changed since the last draw are written, so the renderer assumes nothing else writes to the terminal
+//Chars are assumed to be a single column wide
+#[derive(Clone, Debug)]
+pub struct TerminalRenderer<C> {
+    color_mode: AnsiColorMode,
+    fallback: char,
+    orientation_fallback: OrientationFallback,
+    //What the terminal is currently showing, None when it has to be redrawn from scratch
+    shown: Option<BunnyGrid<C>>,
+}
+
+impl<C> TerminalRenderer<C>
+where
+    C: Color,
+{
+    pub fn new(color_mode: AnsiColorMode) -> Self {
+        Self {
+            color_mode,
+            fallback: '?',
+            orientation_fallback: OrientationFallback::Upright,
+            shown: None,
+        }
+    }
+
+    //The char drawn for glyphs the charmap has no char for
+    pub fn fallback(self, fallback: char) -> Self {
+        Self { fallback, ..self }
+    }
+
+    pub fn orientation_fallback(self, orientation_fallback: OrientationFallback) -> Self {
+        Self {
+            orientation_fallback,
+            ..self
+        }
+    }
+
+    //Makes the next draw redraw every cell, for when the terminal was cleared or resized
+    pub fn invalidate(&mut self) {
+        self.shown = None;
+    }
+
+    //Switches to the alternate screen and hides the cursor
+    pub fn enter<W>(&mut self, out: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.invalidate();
+        enter_screen(out)
+    }
+
+    //Restores the screen and cursor the terminal had before enter
+    pub fn leave<W>(&mut self, out: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.invalidate();
+        leave_screen(out)
+    }
+
+    //Writes the cells that differ from the last drawn grid, with the grid's top left cell at the top left of the terminal
+    pub fn draw<W>(&mut self, out: &mut W, grid: &BunnyGrid<C>, charmap: &CharMap) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let (clear, changes) = match &self.shown {
+            Some(shown) if shown.dimensions() == grid.dimensions() => {
+                (false, BunnyGrid::diff(shown, grid).changes)
+            }
+            _ => (true, BunnyGrid::diff(&BunnyGrid::new((0, 0)), grid).changes),
+        };
+
+        let cells = changes.into_iter().map(|change| {
+            let cell = match &change.cell {
+                Some(bunny_char) => (
+                    Some(self.sgr_parameters(bunny_char)),
+                    self.cell_char(bunny_char, charmap),
+                ),
+                None => (None, ' '),
+            };
+
+            (change.pos, cell)
+        });
+
+        write_cells(out, clear, cells)?;
+
+        self.shown = Some(grid.clone());
+        Ok(())
+    }
+
+    //The char a cell is drawn as, using a char that looks like the rotated or mirrored glyph when there is one
+    fn cell_char(&self, bunny_char: &BunnyChar<C>, charmap: &CharMap) -> char {
+        let ch = match charmap.char_of(bunny_char.index) {
+            Some(ch) if !ch.is_control() => ch,
+            _ => return self.fallback,
+        };
+
+        let upright =
+            (bunny_char.rotation, bunny_char.mirror) == (CharRotation::None, CharMirror::None);
+
+        match oriented_char(ch, bunny_char.rotation, bunny_char.mirror) {
+            Some(oriented) => oriented,
+            None if upright => ch,
+            None => match self.orientation_fallback {
+                OrientationFallback::Upright => ch,
+                OrientationFallback::Char(fallback) => fallback,
+            },
+        }
+    }
+
+    //The SGR parameters setting a cell's colours, after a reset
+    fn sgr_parameters(&self, bunny_char: &BunnyChar<C>) -> String {
+        let mut parameters = sgr_color(self.color_mode, bunny_char.foreground, 30);
+
+        if let Some(background) = bunny_char.background {
+            parameters += ";";
+            parameters += &sgr_color(self.color_mode, background, 40);
+        }
+
+        parameters
+    }
+}
+
+//Draws grids to a terminal with the font's actual glyph pixels, each char cell of the terminal showing two pixels stacked with a half block
+//Every pixel of a glyph takes a column and half a row of the terminal, so an 8x8 glyph covers 8 columns and 4 rows
+//Pixels that are mostly transparent are left showing the terminal's own background
+#[derive(Clone, Debug)]
+pub struct HalfBlockRenderer<C> {
+    color_mode: AnsiColorMode,
+    //What the terminal is currently showing, None when it has to be redrawn from scratch
+    shown: Option<HalfBlockFrame<C>>,
+}
+
+//The top and bottom pixel of every char cell of the terminal, row by row
+#[derive(Clone, Debug, PartialEq)]
+struct HalfBlockFrame<C> {
+    dimensions: (usize, usize),
+    cells: Vec<(Option<C>, Option<C>)>,
+}
+
+impl<C> HalfBlockRenderer<C>
+where
+    C: Color + Lerpable + IntoScalar,
+{
+    pub fn new(color_mode: AnsiColorMode) -> Self {
+        Self {
+            color_mode,
+            shown: None,
+        }
+    }
+
+    //Makes the next draw redraw every cell, for when the terminal was cleared or resized
+    pub fn invalidate(&mut self) {
+        self.shown = None;
+    }
+
+    //Switches to the alternate screen and hides the cursor
+    pub fn enter<W>(&mut self, out: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.invalidate();
+        enter_screen(out)
+    }
+
+    //Restores the screen and cursor the terminal had before enter
+    pub fn leave<W>(&mut self, out: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.invalidate();
+        leave_screen(out)
+    }
+
+    //The number of terminal columns and rows a grid takes up when drawn with a font
+    pub fn terminal_dimensions<T>(grid: &BunnyGrid<C>, font: &BunnyFont<T>) -> (usize, usize)
+    where
+        T: SourceImage<Color = C>,
+    {
+        let (width, height) = grid.dimensions();
+        let (char_width, char_height) = font.char_dimensions();
+
+        (width * char_width, (height * char_height).div_ceil(2))
+    }
+
+    //Writes the terminal cells that differ from the last drawn grid, with the grid's top left pixel at the top left of the terminal
+    pub fn draw<W, T>(
+        &mut self,
+        out: &mut W,
+        grid: &BunnyGrid<C>,
+        font: &BunnyFont<T>,
+    ) -> io::Result<()>
+    where
+        W: Write,
+        T: SourceImage<Color = C> + PixelIndexable<C>,
+    {
+        let frame = Self::frame(grid, font);
+        let (width, _) = frame.dimensions;
+
+        let (clear, changed): (bool, Vec<usize>) = match &self.shown {
+            Some(shown) if shown.dimensions == frame.dimensions => (
+                false,
+                (0..frame.cells.len())
+                    .filter(|&index| shown.cells[index] != frame.cells[index])
+                    .collect(),
+            ),
+            _ => (true, (0..frame.cells.len()).collect()),
+        };
+
+        let cells = changed.into_iter().map(|index| {
+            let cell = match frame.cells[index] {
+                (None, None) => (None, ' '),
+                (Some(top), None) => (Some(sgr_color(self.color_mode, top, 30)), '▀'),
+                (None, Some(bottom)) => (Some(sgr_color(self.color_mode, bottom, 30)), '▄'),
+                (Some(top), Some(bottom)) => (
+                    Some(format!(
+                        "{};{}",
+                        sgr_color(self.color_mode, top, 30),
+                        sgr_color(self.color_mode, bottom, 40)
+                    )),
+                    '▀',
+                ),
+            };
+
+            ((index % width, index / width), cell)
+        });
+
+        write_cells(out, clear, cells)?;
+
+        self.shown = Some(frame);
+        Ok(())
+    }
+
+    fn frame<T>(grid: &BunnyGrid<C>, font: &BunnyFont<T>) -> HalfBlockFrame<C>
+    where
+        T: SourceImage<Color = C> + PixelIndexable<C>,
+    {
+        let (char_width, char_height) = font.char_dimensions();
+        let (width, height) = Self::terminal_dimensions(grid, font);
+
+        let (_, grid_height) = grid.dimensions();
+
+        //Grids with an odd number of pixel rows have a last row of terminal cells with nothing in their bottom half
+        let pixel = |x: usize, y: usize| {
+            if y >= grid_height * char_height {
+                return None;
+            }
+
+            let bunny_char = grid.get(x / char_width, y / char_height)?;
+            let color = font.char_pixel(bunny_char, x % char_width, y % char_height);
+
+            if color.to_rgba8()[3] < 128 {
+                None
+            } else {
+                Some(color)
+            }
+        };
+
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| (pixel(x, y * 2), pixel(x, y * 2 + 1)))
+            .collect();
+
+        HalfBlockFrame {
+            dimensions: (width, height),
+            cells,
+        }
+    }
+}
+
+fn enter_screen<W>(out: &mut W) -> io::Result<()>
+where
+    W: Write,
+{
+    out.write_all(b"\x1b[?1049h\x1b[?25l\x1b[2J")?;
+    out.flush()
+}
+
+fn leave_screen<W>(out: &mut W) -> io::Result<()>
+where
+    W: Write,
+{
+    out.write_all(b"\x1b[0m\x1b[?25h\x1b[?1049l")?;
+    out.flush()
+}
+
+//The SGR parameters setting the foreground or background, with base 30 for foregrounds and 40 for backgrounds
+fn sgr_color<C>(color_mode: AnsiColorMode, color: C, base: usize) -> String
+where
+    C: Color,
+{
+    let [r, g, b, _] = color.to_rgba8();
+
+    //Terminals have their own codes for the bright colours, unlike ANSI art which uses bold and blink
+    match color_mode {
+        AnsiColorMode::Ansi16 => {
+            let index = nearest_color(&VGA_PALETTE, [r, g, b]);
+            let bright = if index > 7 { 60 } else { 0 };
+            format!("{}", base + bright + index % 8)
+        }
+        AnsiColorMode::Ansi256 => format!("{};5;{}", base + 8, nearest_xterm_color([r, g, b])),
+        AnsiColorMode::TrueColor => format!("{};2;{};{};{}", base + 8, r, g, b),
+    }
+}
+
+//Writes cells as their SGR parameters, None for the default style, and char, clearing the screen first if asked
+//The cursor is only moved and the style only changed when needed
+fn write_cells<W, I>(out: &mut W, clear: bool, cells: I) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = ((usize, usize), (Option<String>, char))>,
+{
+    let mut bytes = Vec::new();
+
+    if clear {
+        bytes.extend(b"\x1b[0m\x1b[2J");
+    }
+
+    //The cursor position and style after the last write
+    //Every draw ends with a reset, so the terminal starts out with the default style
+    let mut cursor = None;
+    let mut style = None;
+
+    for ((x, y), (cell_style, ch)) in cells {
+        if cursor != Some((x, y)) {
+            bytes.extend(format!("\x1b[{};{}H", y + 1, x + 1).bytes());
+        }
+
+        if cell_style != style {
+            match &cell_style {
+                Some(parameters) => bytes.extend(format!("\x1b[0;{}m", parameters).bytes()),
+                None => bytes.extend(b"\x1b[0m"),
+            }
+
+            style = cell_style;
+        }
+
+        let mut encoded = [0; 4];
+        bytes.extend(ch.encode_utf8(&mut encoded).bytes());
+
+        cursor = Some((x + 1, y));
+    }
+
+    if bytes.is_empty() {
+        return Ok(());
+    }
+
+    bytes.extend(b"\x1b[0m");
+
+    out.write_all(&bytes)?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::color::TestColor;
+
+    const RED: TestColor = TestColor([170, 0, 0, 255]);
+
+    fn cell(index: usize) -> Option<BunnyChar<TestColor>> {
+        Some(BunnyChar::new(
+            index,
+            RED,
+            None,
+            CharRotation::None,
+            CharMirror::None,
+        ))
+    }
+
+    fn draw(renderer: &mut TerminalRenderer<TestColor>, grid: &BunnyGrid<TestColor>) -> String {
+        let mut out = Vec::new();
+        renderer.draw(&mut out, grid, &CharMap::cp437()).unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn draws_only_what_changed() {
+        let mut renderer = TerminalRenderer::new(AnsiColorMode::Ansi16);
+        let mut grid = BunnyGrid::new((3, 2));
+        grid.set(0, 0, cell(b'A' as usize));
+
+        assert_eq!(
+            draw(&mut renderer, &grid),
+            "\x1b[0m\x1b[2J\x1b[1;1H\x1b[0;31mA\x1b[0m  \x1b[2;1H   \x1b[0m"
+        );
+
+        grid.set(2, 1, cell(b'B' as usize));
+
+        assert_eq!(draw(&mut renderer, &grid), "\x1b[2;3H\x1b[0;31mB\x1b[0m");
+        assert_eq!(draw(&mut renderer, &grid), "");
+
+        renderer.invalidate();
+
+        //A grid of a different size is drawn from scratch
+        let grid = BunnyGrid::new((1, 1));
+
+        assert_eq!(
+            draw(&mut renderer, &grid),
+            "\x1b[0m\x1b[2J\x1b[1;1H \x1b[0m"
+        );
+    }
+
+    #[test]
+    fn cell_chars() {
+        let renderer = TerminalRenderer::<TestColor>::new(AnsiColorMode::Ansi16)
+            .orientation_fallback(OrientationFallback::Char('#'));
+        let charmap = CharMap::cp437().with_char('\t', 300);
+
+        let oriented = |index: usize, rotation| {
+            let bunny_char = BunnyChar::new(index, RED, None, rotation, CharMirror::None);
+            renderer.cell_char(&bunny_char, &charmap)
+        };
+
+        assert_eq!(oriented(b'>' as usize, CharRotation::None), '>');
+        assert_eq!(oriented(b'>' as usize, CharRotation::Rotation90), 'v');
+        assert_eq!(oriented(b'(' as usize, CharRotation::None), '(');
+        assert_eq!(oriented(b'(' as usize, CharRotation::Rotation90), '#');
+        assert_eq!(oriented(300, CharRotation::None), '?');
+        assert_eq!(oriented(1000, CharRotation::None), '?');
+    }
+
+    #[test]
+    fn sgr_colors() {
+        let color = TestColor([255, 85, 85, 255]);
+
+        assert_eq!(sgr_color(AnsiColorMode::Ansi16, RED, 30), "31");
+        assert_eq!(sgr_color(AnsiColorMode::Ansi16, color, 40), "101");
+        assert_eq!(sgr_color(AnsiColorMode::Ansi256, color, 30), "38;5;203");
+        assert_eq!(
+            sgr_color(AnsiColorMode::TrueColor, color, 40),
+            "48;2;255;85;85"
+        );
+    }
+
+    #[cfg(feature = "image-integration")]
+    mod half_block {
+        use super::*;
+        use image::{Rgba, RgbaImage};
+
+        const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+        const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+        //Three chars one pixel wide and three tall, the first blank, the second inked at its top and bottom
+        //and the third inked at only its bottom
+        fn font() -> BunnyFont<RgbaImage> {
+            let mut texture = RgbaImage::new(3, 3);
+            texture.put_pixel(1, 0, Rgba([255; 4]));
+            texture.put_pixel(1, 2, Rgba([255; 4]));
+            texture.put_pixel(2, 2, Rgba([255; 4]));
+
+            BunnyFont::new(texture, (1, 3))
+        }
+
+        fn cell(index: usize, background: Option<Rgba<u8>>) -> Option<BunnyChar<Rgba<u8>>> {
+            Some(BunnyChar::new(
+                index,
+                RED,
+                background,
+                CharRotation::None,
+                CharMirror::None,
+            ))
+        }
+
+        fn draw(renderer: &mut HalfBlockRenderer<Rgba<u8>>, grid: &BunnyGrid<Rgba<u8>>) -> String {
+            let mut out = Vec::new();
+            renderer.draw(&mut out, grid, &font()).unwrap();
+
+            String::from_utf8(out).unwrap()
+        }
+
+        #[test]
+        fn odd_pixel_rows_leave_the_last_bottom_half_empty() {
+            let mut grid = BunnyGrid::new((1, 1));
+            grid.set(0, 0, cell(1, None));
+
+            assert_eq!(
+                HalfBlockRenderer::terminal_dimensions(&grid, &font()),
+                (1, 2)
+            );
+
+            let mut renderer = HalfBlockRenderer::new(AnsiColorMode::Ansi16);
+
+            assert_eq!(
+                draw(&mut renderer, &grid),
+                "\x1b[0m\x1b[2J\x1b[1;1H\x1b[0;31m▀\x1b[2;1H▀\x1b[0m"
+            );
+
+            grid.set(0, 0, cell(2, None));
+
+            assert_eq!(draw(&mut renderer, &grid), "\x1b[1;1H \x1b[0m");
+            assert_eq!(draw(&mut renderer, &grid), "");
+        }
+
+        #[test]
+        fn backgrounds_fill_the_bottom_half() {
+            let mut grid = BunnyGrid::new((1, 1));
+            grid.set(0, 0, cell(2, Some(BLUE)));
+
+            let mut renderer = HalfBlockRenderer::new(AnsiColorMode::Ansi16);
+
+            //The top cell is background on background, the bottom cell has no pixel below the glyph
+            assert_eq!(
+                draw(&mut renderer, &grid),
+                "\x1b[0m\x1b[2J\x1b[1;1H\x1b[0;34;44m▀\x1b[2;1H\x1b[0;31m▀\x1b[0m"
+            );
+        }
+    }
+}
@@ -0,0 +1,454 @@
+use crate::{
+    char::BunnyChar, charmap::CharMap, grid::BunnyGrid, rect::GridRect, traits::color::Color,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HorizontalAlign {
+    Left,
+    Centre,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VerticalAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+//A single source char of some text along with the char it is printed as
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextCell<C> {
+    pub ch: char,
+    pub bunny_char: BunnyChar<C>,
+}
+
+impl<C> TextCell<C>
+where
+    C: Color,
+{
+    pub fn new(ch: char, bunny_char: BunnyChar<C>) -> Self {
+        Self { ch, bunny_char }
+    }
+
+    //Converts a string into text cells, chars missing from the charmap keep the template's index
+    pub fn from_str(text: &str, template: BunnyChar<C>, charmap: &CharMap) -> Vec<Self> {
+        text.chars()
+            .map(|ch| {
+                let lookup = if ch == '\t' || ch == '\n' { ' ' } else { ch };
+                let index = charmap.index_of(lookup).unwrap_or(template.index);
+
+                Self::new(ch, template.index(index))
+            })
+            .collect()
+    }
+
+    fn is_whitespace(&self) -> bool {
+        self.ch.is_whitespace()
+    }
+
+    fn with_ch(self, ch: char, charmap: &CharMap) -> Self {
+        let index = charmap.index_of(ch).unwrap_or(self.bunny_char.index);
+
+        Self::new(ch, self.bunny_char.index(index))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextOptions {
+    pub align: HorizontalAlign,
+    pub vertical_align: VerticalAlign,
+    pub tab_width: usize,
+    pub ellipsis: Option<char>,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            align: HorizontalAlign::Left,
+            vertical_align: VerticalAlign::Top,
+            tab_width: 4,
+            ellipsis: None,
+        }
+    }
+}
+
+impl TextOptions {
+    pub fn align(self, align: HorizontalAlign) -> Self {
+        Self { align, ..self }
+    }
+
+    pub fn vertical_align(self, vertical_align: VerticalAlign) -> Self {
+        Self {
+            vertical_align,
+            ..self
+        }
+    }
+
+    pub fn tab_width(self, tab_width: usize) -> Self {
+        Self { tab_width, ..self }
+    }
+
+    //The char that replaces the last visible char of text that had to be cut off
+    pub fn ellipsis(self, ellipsis: Option<char>) -> Self {
+        Self { ellipsis, ..self }
+    }
+}
+
+//Splits text cells into lines at hard line breaks, expanding tabs and optionally word wrapping to the given width
+pub fn layout_lines<C>(
+    cells: &[TextCell<C>],
+    width: usize,
+    wrap: bool,
+    tab_width: usize,
+) -> Vec<Vec<TextCell<C>>>
+where
+    C: Color,
+{
+    if cells.is_empty() || (wrap && width == 0) {
+        return Vec::new();
+    }
+
+    let mut lines = Vec::new();
+
+    for paragraph in cells.split(|cell| cell.ch == '\n') {
+        let mut line: Vec<TextCell<C>> = Vec::new();
+        let mut wrapped = false;
+
+        let mut rest = paragraph;
+
+        while let Some(first) = rest.first() {
+            let is_whitespace = first.is_whitespace();
+            let token_len = rest
+                .iter()
+                .position(|cell| cell.is_whitespace() != is_whitespace)
+                .unwrap_or(rest.len());
+            let (token, remaining) = rest.split_at(token_len);
+            rest = remaining;
+
+            if is_whitespace {
+                //Whitespace that caused a wrap is dropped rather than starting the next line
+                if wrapped {
+                    continue;
+                }
+
+                for cell in token {
+                    let space = TextCell::new(' ', cell.bunny_char);
+                    let count = if cell.ch == '\t' {
+                        tab_width.max(1) - line.len() % tab_width.max(1)
+                    } else {
+                        1
+                    };
+
+                    for _ in 0..count {
+                        if wrap && line.len() >= width {
+                            trim_trailing_whitespace(&mut line);
+                            lines.push(std::mem::take(&mut line));
+                            wrapped = true;
+                            break;
+                        }
+
+                        line.push(space);
+                    }
+
+                    if wrapped {
+                        break;
+                    }
+                }
+            } else {
+                let mut word = token;
+
+                if wrap && line.len() + word.len() > width && !line.is_empty() {
+                    trim_trailing_whitespace(&mut line);
+
+                    lines.push(std::mem::take(&mut line));
+                }
+
+                //Words that don't fit on a line of their own get broken up
+                while wrap && word.len() > width {
+                    let (head, tail) = word.split_at(width);
+                    lines.push(head.to_vec());
+                    word = tail;
+                }
+
+                line.extend_from_slice(word);
+                wrapped = false;
+            }
+        }
+
+        if wrap {
+            trim_trailing_whitespace(&mut line);
+        }
+
+        if !(wrapped && line.is_empty()) {
+            lines.push(line);
+        }
+    }
+
+    lines
+}
+
+fn trim_trailing_whitespace<C>(line: &mut Vec<TextCell<C>>)
+where
+    C: Color,
+{
+    while line.last().is_some_and(TextCell::is_whitespace) {
+        line.pop();
+    }
+}
+
+impl<C> BunnyGrid<C>
+where
+    C: Color,
+{
+    //Prints text on a single line per hard line break, cutting off anything that doesn't fit in the area
+    pub fn print(
+        &mut self,
+        charmap: &CharMap,
+        area: GridRect,
+        text: &str,
+        template: BunnyChar<C>,
+        options: &TextOptions,
+    ) -> GridRect {
+        let cells = TextCell::from_str(text, template, charmap);
+
+        self.print_cells(charmap, area, &cells, false, options)
+    }
+
+    //Prints text word wrapped to the width of the area
+    pub fn print_wrapped(
+        &mut self,
+        charmap: &CharMap,
+        area: GridRect,
+        text: &str,
+        template: BunnyChar<C>,
+        options: &TextOptions,
+    ) -> GridRect {
+        let cells = TextCell::from_str(text, template, charmap);
+
+        self.print_cells(charmap, area, &cells, true, options)
+    }
+
+    //Prints already styled text cells, returning the area that was actually printed to
+    pub fn print_cells(
+        &mut self,
+        charmap: &CharMap,
+        area: GridRect,
+        cells: &[TextCell<C>],
+        wrap: bool,
+        options: &TextOptions,
+    ) -> GridRect {
+        let area = area.intersection(&GridRect::from_dimensions(self.dimensions()));
+        let mut lines = layout_lines(cells, area.width, wrap, options.tab_width);
+
+        let mut truncated = false;
+
+        if lines.len() > area.height {
+            lines.truncate(area.height);
+            truncated = true;
+        }
+
+        for line in lines.iter_mut() {
+            if line.len() > area.width {
+                line.truncate(area.width);
+
+                if let (Some(ellipsis), Some(last)) = (options.ellipsis, line.last_mut()) {
+                    *last = last.with_ch(ellipsis, charmap);
+                }
+            }
+        }
+
+        if let (true, Some(ellipsis), Some(line)) = (truncated, options.ellipsis, lines.last_mut()) {
+            if let Some(last) = line.last().copied() {
+                if line.len() < area.width {
+                    line.push(last.with_ch(ellipsis, charmap));
+                } else {
+                    *line.last_mut().unwrap() = last.with_ch(ellipsis, charmap);
+                }
+            }
+        }
+
+        let y_offset = match options.vertical_align {
+            VerticalAlign::Top => 0,
+            VerticalAlign::Middle => (area.height - lines.len()) / 2,
+            VerticalAlign::Bottom => area.height - lines.len(),
+        };
+
+        let mut consumed_x = area.right();
+        let mut consumed_right = area.x;
+
+        for (line_index, line) in lines.iter().enumerate() {
+            let x_offset = match options.align {
+                HorizontalAlign::Left => 0,
+                HorizontalAlign::Centre => (area.width - line.len()) / 2,
+                HorizontalAlign::Right => area.width - line.len(),
+            };
+
+            let y = area.y + y_offset + line_index;
+
+            for (char_index, cell) in line.iter().enumerate() {
+                self.set(area.x + x_offset + char_index, y, Some(cell.bunny_char));
+            }
+
+            consumed_x = consumed_x.min(area.x + x_offset);
+            consumed_right = consumed_right.max(area.x + x_offset + line.len());
+        }
+
+        if lines.is_empty() {
+            return GridRect::new(area.x, area.y + y_offset, 0, 0);
+        }
+
+        GridRect::new(
+            consumed_x,
+            area.y + y_offset,
+            consumed_right.saturating_sub(consumed_x),
+            lines.len(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        char_transforms::{CharMirror, CharRotation},
+        traits::color::TestColor,
+    };
+
+    fn template() -> BunnyChar<TestColor> {
+        BunnyChar::new(
+            0,
+            TestColor([255; 4]),
+            None,
+            CharRotation::None,
+            CharMirror::None,
+        )
+    }
+
+    fn layout(text: &str, width: usize, wrap: bool, tab_width: usize) -> Vec<String> {
+        let cells = TextCell::from_str(text, template(), &CharMap::cp437());
+
+        layout_lines(&cells, width, wrap, tab_width)
+            .iter()
+            .map(|line| line.iter().map(|cell| cell.ch).collect())
+            .collect()
+    }
+
+    fn row(grid: &BunnyGrid<TestColor>, y: usize) -> String {
+        let charmap = CharMap::cp437();
+        let (width, _) = grid.dimensions();
+
+        (0..width)
+            .map(|x| {
+                grid.get(x, y)
+                    .and_then(|bunny_char| charmap.char_of(bunny_char.index))
+                    .unwrap_or('.')
+            })
+            .collect()
+    }
+
+    #[test]
+    fn wraps_at_word_boundaries() {
+        assert_eq!(
+            layout("the quick brown fox", 10, true, 4),
+            vec!["the quick", "brown fox"]
+        );
+    }
+
+    #[test]
+    fn breaks_words_longer_than_a_line() {
+        assert_eq!(
+            layout("abcdefgh ij", 3, true, 4),
+            vec!["abc", "def", "gh", "ij"]
+        );
+    }
+
+    #[test]
+    fn keeps_hard_line_breaks_and_empty_lines() {
+        assert_eq!(layout("a\n\nb", 10, true, 4), vec!["a", "", "b"]);
+        assert_eq!(layout("a\nb", 10, false, 4), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn expands_tabs_to_the_next_stop() {
+        assert_eq!(layout("a\tb", 10, false, 4), vec!["a   b"]);
+        assert_eq!(layout("abcd\tb", 10, false, 4), vec!["abcd    b"]);
+        assert_eq!(layout("\t\tx", 10, false, 2), vec!["    x"]);
+    }
+
+    #[test]
+    fn drops_whitespace_that_causes_a_wrap() {
+        assert_eq!(layout("abc\tdef", 4, true, 4), vec!["abc", "def"]);
+        assert_eq!(layout("ab    cd", 4, true, 4), vec!["ab", "cd"]);
+    }
+
+    #[test]
+    fn does_not_wrap_without_wrapping() {
+        assert_eq!(
+            layout("the quick brown", 5, false, 4),
+            vec!["the quick brown"]
+        );
+    }
+
+    #[test]
+    fn zero_width_wraps_to_nothing() {
+        assert!(layout("abc", 0, true, 4).is_empty());
+    }
+
+    #[test]
+    fn print_aligns_and_reports_the_printed_area() {
+        let mut grid = BunnyGrid::new((7, 3));
+        let options = TextOptions::default()
+            .align(HorizontalAlign::Right)
+            .vertical_align(VerticalAlign::Bottom);
+
+        let printed = grid.print(
+            &CharMap::cp437(),
+            GridRect::from_dimensions((7, 3)),
+            "ab\nc",
+            template(),
+            &options,
+        );
+
+        assert_eq!(printed, GridRect::new(5, 1, 2, 2));
+        assert_eq!(row(&grid, 1), ".....ab");
+        assert_eq!(row(&grid, 2), "......c");
+    }
+
+    #[test]
+    fn print_marks_cut_off_text_with_the_ellipsis() {
+        let mut grid = BunnyGrid::new((5, 1));
+        let options = TextOptions::default().ellipsis(Some('…'));
+        let charmap = CharMap::cp437().with_char('…', 0xfa);
+
+        grid.print_wrapped(
+            &charmap,
+            GridRect::from_dimensions((5, 1)),
+            "one two three",
+            template(),
+            &options,
+        );
+
+        assert_eq!(
+            grid.get(3, 0).map(|bunny_char| bunny_char.index),
+            Some(0xfa)
+        );
+        assert_eq!(grid.get(4, 0), None);
+    }
+
+    #[test]
+    fn print_clips_to_the_grid() {
+        let mut grid = BunnyGrid::new((3, 1));
+
+        let printed = grid.print(
+            &CharMap::cp437(),
+            GridRect::new(1, 0, 10, 5),
+            "abcdef",
+            template(),
+            &TextOptions::default(),
+        );
+
+        assert_eq!(printed, GridRect::new(1, 0, 2, 1));
+        assert_eq!(row(&grid, 0), ".ab");
+    }
+}
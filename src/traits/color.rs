@@ -1,2 +1,23 @@
-pub trait Color {
+//Colours are compared and converted to and from RGBA bytes so that grids can be diffed and exported
+//Since 0.2.0 this is no longer an empty marker trait, so existing impls need to provide these conversions
+pub trait Color: Copy + PartialEq {
+    fn from_rgba8(rgba: [u8; 4]) -> Self;
+
+    fn to_rgba8(&self) -> [u8; 4];
+}
+
+//A plain colour for tests, so that they don't depend on an integration being enabled
+#[cfg(test)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct TestColor(pub [u8; 4]);
+
+#[cfg(test)]
+impl Color for TestColor {
+    fn from_rgba8(rgba: [u8; 4]) -> Self {
+        TestColor(rgba)
+    }
+
+    fn to_rgba8(&self) -> [u8; 4] {
+        self.0
+    }
 }
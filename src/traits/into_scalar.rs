@@ -1,3 +1,4 @@
 pub trait IntoScalar {
+    #[allow(clippy::wrong_self_convention)]
     fn into_scalar(&self) -> f32;
 }
\ No newline at end of file
@@ -5,3 +5,16 @@ pub trait SourceImage {
 
     fn get_pixel_dimensions(&self) -> (usize, usize);
 }
+
+//A font sized image without pixels, for tests that only need its dimensions
+#[cfg(test)]
+pub(crate) struct TestImage(pub (usize, usize));
+
+#[cfg(test)]
+impl SourceImage for TestImage {
+    type Color = crate::traits::color::TestColor;
+
+    fn get_pixel_dimensions(&self) -> (usize, usize) {
+        self.0
+    }
+}
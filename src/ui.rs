@@ -0,0 +1,947 @@
+use crate::{
+    border::BorderStyle,
+    char::BunnyChar,
+    char_transforms::{CharMirror, CharRotation},
+    charmap::CharMap,
+    grid::BunnyGrid,
+    rect::GridRect,
+    text::{layout_lines, HorizontalAlign, TextCell, TextOptions, VerticalAlign},
+    traits::color::Color,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UiKey {
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Tab,
+    Enter,
+    Space,
+    Escape,
+}
+
+//Input for a single frame, with the mouse already converted to cell coordinates
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UiInput {
+    pub mouse: Option<(usize, usize)>,
+    pub clicked: bool,
+    pub scroll: i32,
+    pub keys: Vec<UiKey>,
+}
+
+impl UiInput {
+    pub fn pressed(&self, key: UiKey) -> bool {
+        self.keys.contains(&key)
+    }
+
+    pub fn hovered(&self, area: GridRect) -> bool {
+        self.mouse.is_some_and(|(x, y)| area.contains(x, y))
+    }
+
+    pub fn clicked_in(&self, area: GridRect) -> bool {
+        self.clicked && self.hovered(area)
+    }
+}
+
+//Colours and glyph names used by widgets, glyph names are resolved through the charmap
+#[derive(Clone, Debug, PartialEq)]
+pub struct UiTheme<C> {
+    pub foreground: C,
+    pub background: Option<C>,
+    pub hover_foreground: C,
+    pub hover_background: Option<C>,
+    pub selected_foreground: C,
+    pub selected_background: Option<C>,
+    pub border: Option<BorderStyle>,
+    pub checked_glyph: String,
+    pub unchecked_glyph: String,
+    pub scrollbar_track_glyph: String,
+    pub scrollbar_thumb_glyph: String,
+    //Partially filled bar glyphs from emptiest to fullest, the last one being a completely filled cell
+    pub bar_glyphs: Vec<String>,
+    //Marks cut off labels and dialog text, which are cut off cleanly when the charmap has no such glyph
+    pub ellipsis_glyph: String,
+}
+
+impl<C> UiTheme<C>
+where
+    C: Color,
+{
+    pub fn new(
+        foreground: C,
+        background: Option<C>,
+        highlight: C,
+        highlight_background: Option<C>,
+    ) -> Self {
+        Self {
+            foreground,
+            background,
+            hover_foreground: highlight,
+            hover_background: background,
+            selected_foreground: foreground,
+            selected_background: highlight_background,
+            border: None,
+            checked_glyph: "√".to_owned(),
+            unchecked_glyph: " ".to_owned(),
+            scrollbar_track_glyph: "░".to_owned(),
+            scrollbar_thumb_glyph: "█".to_owned(),
+            bar_glyphs: ["▏", "▎", "▍", "▌", "▋", "▊", "▉", "█"]
+                .iter()
+                .map(|glyph| glyph.to_string())
+                .collect(),
+            ellipsis_glyph: "…".to_owned(),
+        }
+    }
+
+    pub fn border(self, border: Option<BorderStyle>) -> Self {
+        Self { border, ..self }
+    }
+
+    fn template(&self, foreground: C, background: Option<C>) -> BunnyChar<C> {
+        BunnyChar::new(
+            0,
+            foreground,
+            background,
+            CharRotation::None,
+            CharMirror::None,
+        )
+    }
+
+    pub fn normal(&self) -> BunnyChar<C> {
+        self.template(self.foreground, self.background)
+    }
+
+    pub fn hovered(&self) -> BunnyChar<C> {
+        self.template(self.hover_foreground, self.hover_background)
+    }
+
+    pub fn selected(&self) -> BunnyChar<C> {
+        self.template(self.selected_foreground, self.selected_background)
+    }
+}
+
+//Scroll position and selection of a list, owned by the caller between frames
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ListState {
+    pub selected: usize,
+    pub offset: usize,
+}
+
+impl ListState {
+    //Scrolls just far enough to bring the selected item into view
+    pub fn scroll_to_selected(&mut self, visible: usize) {
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if visible > 0 && self.selected >= self.offset + visible {
+            self.offset = self.selected + 1 - visible;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DialogState {
+    pub selected: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DialogResponse {
+    Pending,
+    Chosen(usize),
+    Cancelled,
+}
+
+//An immediate mode UI drawing into a grid, widgets are drawn and handle their input in a single call
+pub struct Ui<'a, C> {
+    grid: &'a mut BunnyGrid<C>,
+    charmap: &'a CharMap,
+    theme: &'a UiTheme<C>,
+    input: &'a UiInput,
+    modal: bool,
+}
+
+impl<'a, C> Ui<'a, C>
+where
+    C: Color,
+{
+    pub fn new(
+        grid: &'a mut BunnyGrid<C>,
+        charmap: &'a CharMap,
+        theme: &'a UiTheme<C>,
+        input: &'a UiInput,
+    ) -> Self {
+        Self {
+            grid,
+            charmap,
+            theme,
+            input,
+            modal: false,
+        }
+    }
+
+    pub fn grid(&mut self) -> &mut BunnyGrid<C> {
+        self.grid
+    }
+
+    //Stops every widget but dialogs from receiving input for the rest of the frame
+    pub fn begin_modal(&mut self) {
+        self.modal = true;
+    }
+
+    fn input(&self) -> Option<&UiInput> {
+        if self.modal {
+            None
+        } else {
+            Some(self.input)
+        }
+    }
+
+    fn hovered(&self, area: GridRect) -> bool {
+        self.input().is_some_and(|input| input.hovered(area))
+    }
+
+    fn clicked(&self, area: GridRect) -> bool {
+        self.input().is_some_and(|input| input.clicked_in(area))
+    }
+
+    fn pressed(&self, key: UiKey, focused: bool) -> bool {
+        focused && self.input().is_some_and(|input| input.pressed(key))
+    }
+
+    fn glyph(&self, name: &str) -> usize {
+        self.charmap
+            .glyph(name)
+            .or_else(|| self.charmap.index_of(' '))
+            .unwrap_or(0)
+    }
+
+    //The char of the theme's ellipsis glyph, as text is printed from chars rather than glyph indices
+    fn ellipsis(&self) -> Option<char> {
+        self.charmap
+            .glyph(&self.theme.ellipsis_glyph)
+            .and_then(|index| self.charmap.char_of(index))
+    }
+
+    fn fill(&mut self, area: GridRect, template: BunnyChar<C>) {
+        let blank = template.index(self.glyph(" "));
+
+        for (x, y) in area.cells() {
+            self.grid.set_clipped(x as i32, y as i32, Some(blank));
+        }
+    }
+
+    fn label(
+        &mut self,
+        area: GridRect,
+        text: &str,
+        template: BunnyChar<C>,
+        align: HorizontalAlign,
+    ) {
+        let options = TextOptions::default()
+            .align(align)
+            .vertical_align(VerticalAlign::Middle)
+            .ellipsis(self.ellipsis());
+
+        self.grid
+            .print(self.charmap, area, text, template, &options);
+    }
+
+    //A clickable label, returns true when clicked or when Enter is pressed while focused
+    pub fn button(&mut self, area: GridRect, label: &str, focused: bool) -> bool {
+        let template = if focused {
+            self.theme.selected()
+        } else if self.hovered(area) {
+            self.theme.hovered()
+        } else {
+            self.theme.normal()
+        };
+
+        self.fill(area, template);
+        self.label(area, label, template, HorizontalAlign::Centre);
+
+        self.clicked(area) || self.pressed(UiKey::Enter, focused)
+    }
+
+    //A labelled tick box, returns true if the value was toggled this frame
+    pub fn checkbox(
+        &mut self,
+        area: GridRect,
+        label: &str,
+        checked: &mut bool,
+        focused: bool,
+    ) -> bool {
+        let toggled = self.clicked(area)
+            || self.pressed(UiKey::Enter, focused)
+            || self.pressed(UiKey::Space, focused);
+
+        if toggled {
+            *checked = !*checked;
+        }
+
+        let template = if focused {
+            self.theme.selected()
+        } else if self.hovered(area) {
+            self.theme.hovered()
+        } else {
+            self.theme.normal()
+        };
+
+        self.fill(area, template);
+
+        let tick = if *checked {
+            &self.theme.checked_glyph
+        } else {
+            &self.theme.unchecked_glyph
+        };
+        let tick = template.index(self.glyph(tick));
+
+        let (x, y) = (area.x as i32, area.y as i32);
+        self.grid
+            .set_clipped(x, y, Some(template.index(self.glyph("["))));
+        self.grid.set_clipped(x + 1, y, Some(tick));
+        self.grid
+            .set_clipped(x + 2, y, Some(template.index(self.glyph("]"))));
+
+        let label_area = GridRect::new(area.x + 4, area.y, area.width.saturating_sub(4), 1);
+        self.label(label_area, label, template, HorizontalAlign::Left);
+
+        toggled
+    }
+
+    //A scrolling list of items, returns the index of an item when it is clicked or Enter is pressed on it
+    pub fn list(
+        &mut self,
+        area: GridRect,
+        items: &[&str],
+        state: &mut ListState,
+        focused: bool,
+    ) -> Option<usize> {
+        if area.is_empty() {
+            return None;
+        }
+
+        let visible = area.height;
+        let last = items.len().saturating_sub(1);
+        let mut activated = None;
+
+        if self.pressed(UiKey::Up, focused) {
+            state.selected = state.selected.saturating_sub(1);
+        }
+        if self.pressed(UiKey::Down, focused) {
+            state.selected = (state.selected + 1).min(last);
+        }
+        if self.pressed(UiKey::PageUp, focused) {
+            state.selected = state.selected.saturating_sub(visible);
+        }
+        if self.pressed(UiKey::PageDown, focused) {
+            state.selected = (state.selected + visible).min(last);
+        }
+        if self.pressed(UiKey::Home, focused) {
+            state.selected = 0;
+        }
+        if self.pressed(UiKey::End, focused) {
+            state.selected = last;
+        }
+
+        state.selected = state.selected.min(last);
+        state.scroll_to_selected(visible);
+
+        if let Some(input) = self.input().filter(|input| input.hovered(area)) {
+            let max_offset = items.len().saturating_sub(visible);
+            state.offset =
+                (state.offset as i64 + input.scroll as i64).clamp(0, max_offset as i64) as usize;
+        }
+
+        if self.pressed(UiKey::Enter, focused) && !items.is_empty() {
+            activated = Some(state.selected);
+        }
+
+        let scrollbar = items.len() > visible;
+        let item_width = if scrollbar {
+            area.width - 1
+        } else {
+            area.width
+        };
+
+        for row in 0..visible {
+            let index = state.offset + row;
+            let row_area = GridRect::new(area.x, area.y + row, item_width, 1);
+
+            if self.clicked(row_area) && index < items.len() {
+                state.selected = index;
+                activated = Some(index);
+            }
+
+            let template = if index == state.selected && index < items.len() {
+                self.theme.selected()
+            } else if self.hovered(row_area) && index < items.len() {
+                self.theme.hovered()
+            } else {
+                self.theme.normal()
+            };
+
+            self.fill(row_area, template);
+
+            if let Some(item) = items.get(index) {
+                self.label(row_area, item, template, HorizontalAlign::Left);
+            }
+        }
+
+        if scrollbar {
+            let track = GridRect::new(area.right() - 1, area.y, 1, visible);
+            self.scrollbar(track, state.offset, visible, items.len());
+        }
+
+        activated
+    }
+
+    //A vertical scrollbar whose thumb shows which part of the content is visible
+    pub fn scrollbar(&mut self, area: GridRect, offset: usize, visible: usize, total: usize) {
+        let template = self.theme.normal();
+        let track = template.index(self.glyph(&self.theme.scrollbar_track_glyph));
+        let thumb = template.index(self.glyph(&self.theme.scrollbar_thumb_glyph));
+
+        self.grid
+            .draw_scrollbar(area, offset, visible, total, track, thumb);
+    }
+
+    //A horizontal bar filled to the given fraction using the theme's partially filled glyphs, with an optional centred label
+    pub fn progress_bar(&mut self, area: GridRect, fraction: f32, fill: C, label: Option<&str>) {
+        let empty = self.theme.normal();
+        let filled = empty.foreground(fill);
+
+        //Only the partial glyphs the font actually has are used, along with how full each of them is
+        let steps: Vec<(usize, f32)> = self
+            .theme
+            .bar_glyphs
+            .iter()
+            .enumerate()
+            .filter_map(|(step, name)| {
+                let fullness = (step + 1) as f32 / self.theme.bar_glyphs.len() as f32;
+                self.charmap.glyph(name).map(|index| (index, fullness))
+            })
+            .collect();
+
+        let cells_filled = fraction.clamp(0.0, 1.0) * area.width as f32;
+
+        for column in 0..area.width {
+            let coverage = (cells_filled - column as f32).clamp(0.0, 1.0);
+
+            let glyph = steps
+                .iter()
+                .rev()
+                .find(|(_, fullness)| *fullness <= coverage + f32::EPSILON)
+                .map(|(index, _)| filled.index(*index))
+                .unwrap_or_else(|| empty.index(self.glyph(" ")));
+
+            for row in 0..area.height {
+                self.grid
+                    .set_clipped((area.x + column) as i32, (area.y + row) as i32, Some(glyph));
+            }
+        }
+
+        if let Some(label) = label {
+            let cells = TextCell::from_str(label, empty, self.charmap);
+            let label_width = cells.len().min(area.width);
+            let label_x = area.x + (area.width - label_width) / 2;
+            let label_y = area.y + area.height / 2;
+
+            //Label chars over the filled part of the bar take the fill colour as their background
+            for (offset, cell) in cells.iter().take(label_width).enumerate() {
+                let column = label_x - area.x + offset;
+                let background = if cells_filled > column as f32 + 0.5 {
+                    Some(fill)
+                } else {
+                    empty.background
+                };
+
+                self.grid.set_clipped(
+                    (label_x + offset) as i32,
+                    label_y as i32,
+                    Some(cell.bunny_char.background(background)),
+                );
+            }
+        }
+    }
+
+    //A wrapped text box placed next to an anchor cell, moved to stay on the grid
+    pub fn tooltip(&mut self, anchor: (usize, usize), text: &str, max_width: usize) {
+        let padding = if self.theme.border.is_some() { 1 } else { 0 };
+        let inner_width = max_width.saturating_sub(padding * 2).max(1);
+
+        let cells = TextCell::from_str(text, self.theme.normal(), self.charmap);
+        let lines = layout_lines(&cells, inner_width, true, 4);
+        let text_width = lines.iter().map(Vec::len).max().unwrap_or(0);
+
+        let (grid_width, grid_height) = self.grid.dimensions();
+        let width = (text_width + padding * 2).min(grid_width);
+        let height = (lines.len() + padding * 2).min(grid_height);
+
+        let (anchor_x, anchor_y) = anchor;
+        let x = if anchor_x + 1 + width <= grid_width {
+            anchor_x + 1
+        } else {
+            anchor_x.saturating_sub(width)
+        };
+        let y = if anchor_y + 1 + height <= grid_height {
+            anchor_y + 1
+        } else {
+            anchor_y.saturating_sub(height)
+        };
+
+        let area = GridRect::new(x, y, width, height);
+
+        self.frame(area);
+        self.grid.print_cells(
+            self.charmap,
+            area.inset(padding),
+            &cells,
+            true,
+            &TextOptions::default(),
+        );
+    }
+
+    //Clears an area and draws the theme's border around it, returning the area inside the border
+    pub fn frame(&mut self, area: GridRect) -> GridRect {
+        let template = self.theme.normal();
+
+        self.fill(area, template);
+
+        match &self.theme.border {
+            Some(border) => {
+                self.grid.draw_box(area, border, template);
+                area.inset(1)
+            }
+            None => area,
+        }
+    }
+
+    //A modal dialog centred on the grid, it always receives input even after begin_modal
+    pub fn dialog(
+        &mut self,
+        title: &str,
+        message: &str,
+        buttons: &[&str],
+        state: &mut DialogState,
+    ) -> DialogResponse {
+        let modal = std::mem::replace(&mut self.modal, false);
+        let response = self.dialog_inner(title, message, buttons, state);
+        self.modal = modal;
+
+        response
+    }
+
+    fn dialog_inner(
+        &mut self,
+        title: &str,
+        message: &str,
+        buttons: &[&str],
+        state: &mut DialogState,
+    ) -> DialogResponse {
+        let (grid_width, grid_height) = self.grid.dimensions();
+        let padding = if self.theme.border.is_some() { 1 } else { 0 };
+
+        let buttons_width: usize = buttons
+            .iter()
+            .map(|button| button.chars().count() + 4)
+            .sum();
+        let width = (grid_width * 2 / 3)
+            .max(buttons_width + padding * 2)
+            .max(title.chars().count() + 6)
+            .min(grid_width);
+
+        let cells = TextCell::from_str(message, self.theme.normal(), self.charmap);
+        let lines = layout_lines(&cells, width.saturating_sub(2 + padding * 2), true, 4);
+        let height = (lines.len() + 4 + padding * 2).min(grid_height);
+
+        let area = GridRect::new(
+            (grid_width - width) / 2,
+            (grid_height - height) / 2,
+            width,
+            height,
+        );
+
+        let inner = self.frame(area);
+
+        if padding > 0 {
+            let title_area = GridRect::new(area.x + 2, area.y, area.width.saturating_sub(4), 1);
+            self.label(
+                title_area,
+                title,
+                self.theme.normal(),
+                HorizontalAlign::Centre,
+            );
+        }
+
+        let message_area = GridRect::new(
+            inner.x + 1,
+            inner.y + 1,
+            inner.width.saturating_sub(2),
+            inner.height.saturating_sub(3),
+        );
+        self.grid.print_cells(
+            self.charmap,
+            message_area,
+            &cells,
+            true,
+            &TextOptions::default().ellipsis(self.ellipsis()),
+        );
+
+        if buttons.is_empty() {
+            return if self.input.pressed(UiKey::Escape) {
+                DialogResponse::Cancelled
+            } else {
+                DialogResponse::Pending
+            };
+        }
+
+        let last = buttons.len() - 1;
+
+        if self.input.pressed(UiKey::Left) {
+            state.selected = state.selected.saturating_sub(1);
+        }
+        if self.input.pressed(UiKey::Right) || self.input.pressed(UiKey::Tab) {
+            state.selected = if state.selected >= last {
+                0
+            } else {
+                state.selected + 1
+            };
+        }
+        state.selected = state.selected.min(last);
+
+        let mut response = if self.input.pressed(UiKey::Escape) {
+            DialogResponse::Cancelled
+        } else {
+            DialogResponse::Pending
+        };
+
+        let mut x = inner.x + (inner.width.saturating_sub(buttons_width)) / 2;
+        let y = inner.bottom().saturating_sub(1);
+
+        for (index, label) in buttons.iter().enumerate() {
+            let button_width = label.chars().count() + 2;
+            let button_area = GridRect::new(x + 1, y, button_width, 1);
+
+            if self.button(button_area, label, index == state.selected) {
+                state.selected = index;
+                response = DialogResponse::Chosen(index);
+            }
+
+            x += button_width + 2;
+        }
+
+        response
+    }
+}
+
+impl<C> BunnyGrid<C>
+where
+    C: Color,
+{
+    //Draws a vertical scrollbar down the first column of an area, the thumb shows which part of the content is visible
+    pub fn draw_scrollbar(
+        &mut self,
+        area: GridRect,
+        offset: usize,
+        visible: usize,
+        total: usize,
+        track: BunnyChar<C>,
+        thumb: BunnyChar<C>,
+    ) {
+        if area.is_empty() {
+            return;
+        }
+
+        let height = area.height;
+        let total = total.max(1);
+        let thumb_height = (height * visible / total).clamp(1, height.max(1));
+        let thumb_start = if total > visible {
+            (height - thumb_height) * offset.min(total - visible) / (total - visible)
+        } else {
+            0
+        };
+
+        for row in 0..height {
+            let cell = if row >= thumb_start && row < thumb_start + thumb_height {
+                thumb
+            } else {
+                track
+            };
+
+            self.set_clipped(area.x as i32, (area.y + row) as i32, Some(cell));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::color::TestColor;
+
+    const WHITE: TestColor = TestColor([255, 255, 255, 255]);
+    const YELLOW: TestColor = TestColor([255, 255, 0, 255]);
+    const BLUE: TestColor = TestColor([0, 0, 255, 255]);
+
+    fn theme() -> UiTheme<TestColor> {
+        UiTheme::new(WHITE, None, YELLOW, Some(BLUE))
+    }
+
+    fn input(keys: &[UiKey]) -> UiInput {
+        UiInput {
+            keys: keys.to_vec(),
+            ..UiInput::default()
+        }
+    }
+
+    fn click(x: usize, y: usize) -> UiInput {
+        UiInput {
+            mouse: Some((x, y)),
+            clicked: true,
+            ..UiInput::default()
+        }
+    }
+
+    fn row(grid: &BunnyGrid<TestColor>, y: usize) -> String {
+        let charmap = CharMap::cp437();
+        let (width, _) = grid.dimensions();
+
+        (0..width)
+            .map(|x| {
+                grid.get(x, y)
+                    .and_then(|bunny_char| charmap.char_of(bunny_char.index))
+                    .unwrap_or('.')
+            })
+            .collect()
+    }
+
+    fn scrollbar_column(offset: usize, visible: usize, total: usize, height: usize) -> String {
+        let mut grid = BunnyGrid::new((1, height));
+        let track = theme().normal().index(1);
+        let thumb = theme().normal().index(2);
+
+        grid.draw_scrollbar(
+            GridRect::new(0, 0, 1, height),
+            offset,
+            visible,
+            total,
+            track,
+            thumb,
+        );
+
+        (0..height)
+            .map(
+                |y| match grid.get(0, y).map(|bunny_char| bunny_char.index) {
+                    Some(2) => '#',
+                    Some(_) => '|',
+                    None => '.',
+                },
+            )
+            .collect()
+    }
+
+    #[test]
+    fn scrollbar_thumb_follows_the_offset() {
+        assert_eq!(scrollbar_column(0, 2, 8, 4), "#|||");
+        assert_eq!(scrollbar_column(6, 2, 8, 4), "|||#");
+        assert_eq!(scrollbar_column(0, 4, 4, 4), "####");
+        assert_eq!(scrollbar_column(0, 4, 0, 4), "####");
+    }
+
+    #[test]
+    fn scrollbar_ignores_empty_areas() {
+        let mut grid: BunnyGrid<TestColor> = BunnyGrid::new((1, 1));
+        let cell = theme().normal();
+
+        grid.draw_scrollbar(GridRect::new(0, 0, 1, 0), 0, 2, 10, cell, cell);
+
+        assert_eq!(grid.get(0, 0), None);
+    }
+
+    #[test]
+    fn buttons_activate_on_click_or_enter_when_focused() {
+        let charmap = CharMap::cp437();
+        let theme = theme();
+        let mut grid = BunnyGrid::new((6, 1));
+        let area = GridRect::new(0, 0, 6, 1);
+
+        let clicked = click(3, 0);
+        assert!(Ui::new(&mut grid, &charmap, &theme, &clicked).button(area, "OK", false));
+        assert_eq!(row(&grid, 0), "  OK  ");
+
+        let enter = input(&[UiKey::Enter]);
+        assert!(!Ui::new(&mut grid, &charmap, &theme, &enter).button(area, "OK", false));
+        assert!(Ui::new(&mut grid, &charmap, &theme, &enter).button(area, "OK", true));
+        assert_eq!(grid.get(0, 0).unwrap().background, Some(BLUE));
+    }
+
+    #[test]
+    fn modal_blocks_input_to_other_widgets() {
+        let charmap = CharMap::cp437();
+        let theme = theme();
+        let mut grid = BunnyGrid::new((6, 1));
+        let clicked = click(0, 0);
+
+        let mut ui = Ui::new(&mut grid, &charmap, &theme, &clicked);
+        ui.begin_modal();
+
+        assert!(!ui.button(GridRect::new(0, 0, 6, 1), "OK", false));
+    }
+
+    #[test]
+    fn checkbox_toggles() {
+        let charmap = CharMap::cp437();
+        let theme = theme();
+        let mut grid = BunnyGrid::new((8, 1));
+        let space = input(&[UiKey::Space]);
+        let mut checked = false;
+
+        let toggled = Ui::new(&mut grid, &charmap, &theme, &space).checkbox(
+            GridRect::new(0, 0, 8, 1),
+            "Sound",
+            &mut checked,
+            true,
+        );
+
+        assert!(toggled && checked);
+        //The ellipsis isn't in code page 437, so the label is cut off without one
+        assert_eq!(row(&grid, 0), "[√] Soun");
+    }
+
+    #[test]
+    fn cut_off_labels_end_in_the_themes_ellipsis() {
+        let no_input = UiInput::default();
+        let label = |charmap: &CharMap, ellipsis_glyph: &str| {
+            let theme = UiTheme {
+                ellipsis_glyph: ellipsis_glyph.to_owned(),
+                ..theme()
+            };
+            let mut grid = BunnyGrid::new((5, 1));
+
+            Ui::new(&mut grid, charmap, &theme, &no_input).button(
+                GridRect::new(0, 0, 5, 1),
+                "Options",
+                false,
+            );
+
+            row(&grid, 0)
+        };
+
+        assert_eq!(label(&CharMap::cp437(), "~"), "Opti~");
+        assert_eq!(
+            label(&CharMap::cp437().with_name("ellipsis", 0xfa), "ellipsis"),
+            "Opti·"
+        );
+        assert_eq!(label(&CharMap::cp437(), "…"), "Optio");
+    }
+
+    #[test]
+    fn list_moves_selection_and_scrolls_it_into_view() {
+        let charmap = CharMap::cp437();
+        let theme = theme();
+        let mut grid = BunnyGrid::new((5, 2));
+        let items = ["a", "b", "c", "d"];
+        let mut state = ListState::default();
+        let area = GridRect::new(0, 0, 5, 2);
+
+        let down = input(&[UiKey::Down, UiKey::Down]);
+        Ui::new(&mut grid, &charmap, &theme, &down).list(area, &items, &mut state, true);
+        assert_eq!(
+            state,
+            ListState {
+                selected: 1,
+                offset: 0
+            }
+        );
+
+        let end = input(&[UiKey::End, UiKey::Enter]);
+        let activated =
+            Ui::new(&mut grid, &charmap, &theme, &end).list(area, &items, &mut state, true);
+
+        assert_eq!(activated, Some(3));
+        assert_eq!(
+            state,
+            ListState {
+                selected: 3,
+                offset: 2
+            }
+        );
+        assert_eq!(row(&grid, 0), "c   ░");
+        assert_eq!(row(&grid, 1), "d   █");
+    }
+
+    #[test]
+    fn list_activates_clicked_items() {
+        let charmap = CharMap::cp437();
+        let theme = theme();
+        let mut grid = BunnyGrid::new((5, 3));
+        let mut state = ListState::default();
+        let clicked = click(1, 1);
+
+        let activated = Ui::new(&mut grid, &charmap, &theme, &clicked).list(
+            GridRect::new(0, 0, 5, 3),
+            &["a", "b"],
+            &mut state,
+            false,
+        );
+
+        assert_eq!(activated, Some(1));
+        assert_eq!(state.selected, 1);
+    }
+
+    #[test]
+    fn progress_bar_uses_the_partial_glyphs_the_font_has() {
+        let charmap = CharMap::cp437();
+        let theme = theme();
+        let mut grid = BunnyGrid::new((3, 1));
+        let no_input = UiInput::default();
+
+        Ui::new(&mut grid, &charmap, &theme, &no_input).progress_bar(
+            GridRect::new(0, 0, 3, 1),
+            0.5,
+            YELLOW,
+            None,
+        );
+
+        assert_eq!(row(&grid, 0), "█▌ ");
+        assert_eq!(grid.get(0, 0).unwrap().foreground, YELLOW);
+    }
+
+    #[test]
+    fn dialog_cycles_buttons_and_cancels() {
+        let charmap = CharMap::cp437();
+        let theme = theme().border(BorderStyle::single(&charmap));
+        let mut grid = BunnyGrid::new((30, 10));
+        let mut state = DialogState::default();
+
+        let tab = input(&[UiKey::Tab, UiKey::Enter]);
+        let response = Ui::new(&mut grid, &charmap, &theme, &tab).dialog(
+            "Quit",
+            "Really quit?",
+            &["Yes", "No"],
+            &mut state,
+        );
+        assert_eq!(response, DialogResponse::Chosen(1));
+
+        let escape = input(&[UiKey::Escape]);
+        let mut ui = Ui::new(&mut grid, &charmap, &theme, &escape);
+        ui.begin_modal();
+        let response = ui.dialog("Quit", "Really quit?", &["Yes", "No"], &mut state);
+        assert_eq!(response, DialogResponse::Cancelled);
+    }
+
+    #[test]
+    fn tooltips_stay_on_the_grid() {
+        let charmap = CharMap::cp437();
+        let theme = theme();
+        let mut grid = BunnyGrid::new((10, 3));
+        let no_input = UiInput::default();
+
+        Ui::new(&mut grid, &charmap, &theme, &no_input).tooltip((8, 2), "hey", 10);
+
+        assert_eq!(row(&grid, 1), ".....hey..");
+    }
+}
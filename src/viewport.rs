@@ -0,0 +1,328 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{grid::BunnyGrid, rect::GridRect, traits::color::Color};
+
+//A camera showing part of a larger world grid in a region of the screen grid
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Viewport {
+    //The region of the screen grid the world is shown in
+    pub screen: GridRect,
+    pub world_dimensions: (usize, usize),
+    //The size of the area around the centre of the view that a followed target can move in without scrolling
+    pub dead_zone: (usize, usize),
+    //Whether the camera stops at the edges of the world
+    pub clamp: bool,
+    position: (i32, i32),
+}
+
+impl Viewport {
+    pub fn new(screen: GridRect, world_dimensions: (usize, usize)) -> Self {
+        let mut viewport = Self {
+            screen,
+            world_dimensions,
+            dead_zone: (0, 0),
+            clamp: true,
+            position: (0, 0),
+        };
+
+        viewport.scroll_to(0, 0);
+        viewport
+    }
+
+    pub fn dead_zone(self, dead_zone: (usize, usize)) -> Self {
+        Self { dead_zone, ..self }
+    }
+
+    pub fn clamp(mut self, clamp: bool) -> Self {
+        self.clamp = clamp;
+        let (x, y) = self.position;
+        self.scroll_to(x, y);
+        self
+    }
+
+    //Should be called when the window or the world changes size
+    pub fn resize(&mut self, screen: GridRect, world_dimensions: (usize, usize)) {
+        self.screen = screen;
+        self.world_dimensions = world_dimensions;
+
+        let (x, y) = self.position;
+        self.scroll_to(x, y);
+    }
+
+    //The world position of the top left cell of the view
+    pub fn position(&self) -> (i32, i32) {
+        self.position
+    }
+
+    pub fn scroll_to(&mut self, x: i32, y: i32) {
+        self.position = if self.clamp {
+            (
+                clamp_axis(x, self.screen.width, self.world_dimensions.0),
+                clamp_axis(y, self.screen.height, self.world_dimensions.1),
+            )
+        } else {
+            (x, y)
+        };
+    }
+
+    pub fn scroll_by(&mut self, dx: i32, dy: i32) {
+        let (x, y) = self.position;
+        self.scroll_to(x + dx, y + dy);
+    }
+
+    pub fn centre_on(&mut self, x: i32, y: i32) {
+        self.scroll_to(
+            x - self.screen.width as i32 / 2,
+            y - self.screen.height as i32 / 2,
+        );
+    }
+
+    //Scrolls just enough to keep a target inside the dead zone
+    pub fn follow(&mut self, x: i32, y: i32) {
+        let follow_axis = |position: i32, view: usize, dead_zone: usize, target: i32| {
+            let dead_zone = dead_zone.min(view) as i32;
+            let zone_start = position + (view as i32 - dead_zone) / 2;
+            let zone_end = zone_start + dead_zone.max(1) - 1;
+
+            if target < zone_start {
+                position - (zone_start - target)
+            } else if target > zone_end {
+                position + (target - zone_end)
+            } else {
+                position
+            }
+        };
+
+        let (position_x, position_y) = self.position;
+        let (dead_width, dead_height) = self.dead_zone;
+
+        self.scroll_to(
+            follow_axis(position_x, self.screen.width, dead_width, x),
+            follow_axis(position_y, self.screen.height, dead_height, y),
+        );
+    }
+
+    //The part of the world that is visible, clipped to the world's edges
+    pub fn visible_world(&self) -> GridRect {
+        let (x, y) = self.position;
+        let (world_width, world_height) = self.world_dimensions;
+
+        let clip_axis = |start: i32, length: usize, world_length: usize| {
+            let end = (start + length as i32).clamp(0, world_length as i32);
+            let start = start.clamp(0, world_length as i32);
+
+            (start as usize, (end - start) as usize)
+        };
+
+        let (x, width) = clip_axis(x, self.screen.width, world_width);
+        let (y, height) = clip_axis(y, self.screen.height, world_height);
+
+        GridRect::new(x, y, width, height)
+    }
+
+    //Converts a world cell to a screen grid cell, or None if it isn't visible
+    pub fn world_to_screen(&self, x: i32, y: i32) -> Option<(usize, usize)> {
+        let (position_x, position_y) = self.position;
+        let (view_x, view_y) = (x - position_x, y - position_y);
+
+        if view_x < 0
+            || view_y < 0
+            || view_x as usize >= self.screen.width
+            || view_y as usize >= self.screen.height
+        {
+            return None;
+        }
+
+        Some((
+            self.screen.x + view_x as usize,
+            self.screen.y + view_y as usize,
+        ))
+    }
+
+    //Converts a screen grid cell to a world cell, or None if it is outside the view
+    pub fn screen_to_world(&self, x: usize, y: usize) -> Option<(i32, i32)> {
+        if !self.screen.contains(x, y) {
+            return None;
+        }
+
+        let (position_x, position_y) = self.position;
+
+        Some((
+            position_x + (x - self.screen.x) as i32,
+            position_y + (y - self.screen.y) as i32,
+        ))
+    }
+
+    //Converts a pixel position to a world cell given the on screen size of a tile
+    pub fn pixel_to_world(&self, x: f32, y: f32, tile_size: (f32, f32)) -> Option<(i32, i32)> {
+        let (tile_width, tile_height) = tile_size;
+
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+
+        self.screen_to_world((x / tile_width) as usize, (y / tile_height) as usize)
+    }
+
+    //The pixel position of the top left corner of a world cell given the on screen size of a tile
+    pub fn world_to_pixel(&self, x: i32, y: i32, tile_size: (f32, f32)) -> Option<(f32, f32)> {
+        let (tile_width, tile_height) = tile_size;
+
+        self.world_to_screen(x, y)
+            .map(|(x, y)| (x as f32 * tile_width, y as f32 * tile_height))
+    }
+
+    //Iterates over the visible world cells as ((world_x, world_y), (screen_x, screen_y))
+    pub fn visible_cells(&self) -> impl Iterator<Item = ((usize, usize), (usize, usize))> + '_ {
+        self.visible_world().cells().filter_map(move |(x, y)| {
+            self.world_to_screen(x as i32, y as i32)
+                .map(|screen| ((x, y), screen))
+        })
+    }
+
+    //Copies the visible part of the world onto the screen grid, cells outside of the world are cleared
+    pub fn render<C>(&self, world: &BunnyGrid<C>, screen: &mut BunnyGrid<C>)
+    where
+        C: Color,
+    {
+        for (x, y) in self.screen.cells() {
+            let cell = self
+                .screen_to_world(x, y)
+                .filter(|(world_x, world_y)| world.in_bounds(*world_x, *world_y))
+                .and_then(|(world_x, world_y)| world.get(world_x as usize, world_y as usize))
+                .copied();
+
+            screen.set_clipped(x as i32, y as i32, cell);
+        }
+    }
+}
+
+//Keeps the view inside the world, or centres the world when it is smaller than the view
+fn clamp_axis(position: i32, view: usize, world: usize) -> i32 {
+    if world <= view {
+        -((view - world) as i32 / 2)
+    } else {
+        position.clamp(0, (world - view) as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        char::BunnyChar,
+        char_transforms::{CharMirror, CharRotation},
+        traits::color::TestColor,
+    };
+
+    fn cell(index: usize) -> Option<BunnyChar<TestColor>> {
+        Some(BunnyChar::new(
+            index,
+            TestColor([255; 4]),
+            None,
+            CharRotation::None,
+            CharMirror::None,
+        ))
+    }
+
+    #[test]
+    fn scrolling_is_clamped_to_the_world() {
+        let mut viewport = Viewport::new(GridRect::new(2, 1, 10, 5), (30, 20));
+
+        viewport.scroll_to(-5, 100);
+        assert_eq!(viewport.position(), (0, 15));
+
+        viewport.scroll_by(25, 0);
+        assert_eq!(viewport.position(), (20, 15));
+
+        viewport.centre_on(0, 0);
+        assert_eq!(viewport.position(), (0, 0));
+    }
+
+    #[test]
+    fn small_worlds_are_centred() {
+        let viewport = Viewport::new(GridRect::new(0, 0, 10, 5), (4, 3));
+
+        assert_eq!(viewport.position(), (-3, -1));
+        assert_eq!(viewport.visible_world(), GridRect::new(0, 0, 4, 3));
+    }
+
+    #[test]
+    fn unclamped_viewports_scroll_anywhere() {
+        let mut viewport = Viewport::new(GridRect::new(0, 0, 10, 5), (30, 20)).clamp(false);
+
+        viewport.scroll_to(-5, 100);
+        assert_eq!(viewport.position(), (-5, 100));
+        assert!(viewport.visible_world().is_empty());
+    }
+
+    #[test]
+    fn follow_only_scrolls_outside_the_dead_zone() {
+        let mut viewport = Viewport::new(GridRect::new(0, 0, 10, 5), (100, 100)).dead_zone((4, 1));
+
+        viewport.follow(6, 2);
+        assert_eq!(viewport.position(), (0, 0));
+
+        viewport.follow(20, 10);
+        assert_eq!(viewport.position(), (14, 8));
+
+        viewport.follow(15, 9);
+        assert_eq!(viewport.position(), (12, 7));
+    }
+
+    #[test]
+    fn conversions_between_world_screen_and_pixels() {
+        let mut viewport = Viewport::new(GridRect::new(2, 1, 10, 5), (30, 20));
+        viewport.scroll_to(5, 6);
+
+        assert_eq!(viewport.world_to_screen(5, 6), Some((2, 1)));
+        assert_eq!(viewport.world_to_screen(14, 10), Some((11, 5)));
+        assert_eq!(viewport.world_to_screen(15, 6), None);
+        assert_eq!(viewport.world_to_screen(4, 6), None);
+
+        assert_eq!(viewport.screen_to_world(11, 5), Some((14, 10)));
+        assert_eq!(viewport.screen_to_world(1, 1), None);
+
+        assert_eq!(viewport.pixel_to_world(24.0, 8.0, (8.0, 8.0)), Some((6, 6)));
+        assert_eq!(viewport.pixel_to_world(-1.0, 8.0, (8.0, 8.0)), None);
+        assert_eq!(viewport.world_to_pixel(6, 6, (8.0, 8.0)), Some((24.0, 8.0)));
+    }
+
+    #[test]
+    fn resizing_keeps_the_view_inside_the_world() {
+        let mut viewport = Viewport::new(GridRect::new(0, 0, 10, 5), (30, 20));
+        viewport.scroll_to(20, 15);
+
+        viewport.resize(GridRect::new(0, 0, 10, 5), (25, 18));
+        assert_eq!(viewport.position(), (15, 13));
+    }
+
+    #[test]
+    fn render_clears_cells_outside_the_world() {
+        let mut world = BunnyGrid::new((2, 2));
+
+        for (x, y) in GridRect::new(0, 0, 2, 2).cells() {
+            world.set(x, y, cell(y * 2 + x + 1));
+        }
+
+        let mut screen = BunnyGrid::new((3, 2));
+        screen.fill(cell(9));
+
+        let mut viewport = Viewport::new(GridRect::new(0, 0, 3, 2), (2, 2)).clamp(false);
+        viewport.scroll_to(-1, 0);
+        viewport.render(&world, &mut screen);
+
+        let indices: Vec<Option<usize>> = screen
+            .cells()
+            .map(|(_, bunny_char)| bunny_char.map(|bunny_char| bunny_char.index))
+            .collect();
+
+        assert_eq!(
+            indices,
+            vec![None, Some(1), Some(2), None, Some(3), Some(4)]
+        );
+        assert_eq!(viewport.visible_cells().count(), 4);
+    }
+}